@@ -0,0 +1,135 @@
+//! Player abilities, each unlocked by touching its Feature item
+
+use crate::UniqueItem;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ability {
+    /// Smaller collision radius, to fit through narrow passages
+    SmallSize,
+    /// One more jump in the air, before landing again
+    DoubleJump,
+    /// Holding towards a wall while in the air stops the fall. Jumping off the wall is possible.
+    WallCling,
+    /// Pressing down in the air slams the player to the ground
+    GroundPound,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 4] = [Ability::SmallSize, Ability::DoubleJump, Ability::WallCling, Ability::GroundPound];
+
+    pub const fn feature(self) -> UniqueItem {
+        match self {
+            Ability::SmallSize => UniqueItem::FeatureSmallSize,
+            Ability::DoubleJump => UniqueItem::FeatureDoubleJump,
+            Ability::WallCling => UniqueItem::FeatureWallCling,
+            Ability::GroundPound => UniqueItem::FeatureGroundPound,
+        }
+    }
+
+    /// Shown in the welcome infobox stats once unlocked
+    pub const fn letter(self) -> &'static str {
+        match self {
+            Ability::SmallSize => "S",
+            Ability::DoubleJump => "D",
+            Ability::WallCling => "W",
+            Ability::GroundPound => "P",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{platforms::Platforms, player::Player, tiles::TileType, wasm4::{BUTTON_2, BUTTON_DOWN, BUTTON_RIGHT, BUTTON_UP}, world::World};
+
+    fn frame(player: &mut Player, prev: u8, cur: u8) {
+        player.control(prev, cur);
+        player.simulate_frame(&Platforms::new());
+    }
+
+    fn jump(player: &mut Player) {
+        frame(player, 0, BUTTON_2);
+        frame(player, BUTTON_2, BUTTON_UP);
+    }
+
+    /// Player standing at the bottom of the shaft right of the feature item, next to the wall
+    fn player_at_wall(abilities: &[Ability]) -> Player {
+        let mut tile = UniqueItem::FeatureWallCling.get_pos();
+        while World::get_tile((tile.0 + 1, tile.1)).collision_configuration().is_empty() {
+            tile.0 += 1;
+        }
+        while World::get_tile((tile.0, tile.1 + 1)).collision_configuration().is_empty() {
+            tile.1 += 1;
+        }
+        let mut player = Player::new();
+        player.pos = World::from_world_coords(tile);
+        for &ability in abilities {
+            player.status.touch(ability.feature());
+        }
+        for _ in 0..120 {
+            frame(&mut player, 0, 0);
+        }
+        assert!(player.grounded);
+        player
+    }
+
+    #[test]
+    fn double_jump_once_in_the_air() {
+        for &(abilities, jumps) in &[(&[][..], false), (&[Ability::DoubleJump][..], true)] {
+            let mut player = player_at_wall(abilities);
+            jump(&mut player);
+            for _ in 0..10 {
+                frame(&mut player, 0, 0);
+            }
+            assert!(!player.grounded);
+            let vel = player.vel.im;
+            jump(&mut player);
+            assert_eq!(player.vel.im < vel - 100.0, jumps);
+
+            for _ in 0..10 {
+                frame(&mut player, 0, 0);
+            }
+            let vel = player.vel.im;
+            jump(&mut player);
+            assert!(player.vel.im > vel);
+        }
+    }
+
+    #[test]
+    fn wall_cling_and_jump_off() {
+        for &(abilities, clings) in &[(&[][..], false), (&[Ability::WallCling][..], true)] {
+            let mut player = player_at_wall(abilities);
+            jump(&mut player);
+            for _ in 0..120 {
+                frame(&mut player, BUTTON_RIGHT, BUTTON_RIGHT);
+            }
+            assert_eq!(player.clinging, clings);
+            assert_eq!(player.grounded, !clings);
+        }
+
+        let mut player = player_at_wall(&[Ability::WallCling]);
+        jump(&mut player);
+        for _ in 0..30 {
+            frame(&mut player, BUTTON_RIGHT, BUTTON_RIGHT);
+        }
+        frame(&mut player, BUTTON_RIGHT, BUTTON_2 | BUTTON_RIGHT);
+        frame(&mut player, BUTTON_2 | BUTTON_RIGHT, BUTTON_RIGHT);
+        assert!(player.vel.re < 0.0 && player.vel.im < 0.0);
+    }
+
+    #[test]
+    fn ground_pound_until_landing() {
+        let mut player = player_at_wall(&[Ability::GroundPound]);
+        jump(&mut player);
+        for _ in 0..5 {
+            frame(&mut player, 0, 0);
+        }
+        frame(&mut player, 0, BUTTON_DOWN);
+        assert!(player.pounding);
+        assert!(player.vel.im > 400.0);
+        for _ in 0..60 {
+            frame(&mut player, BUTTON_DOWN, BUTTON_DOWN);
+        }
+        assert!(player.grounded && !player.pounding);
+    }
+}
@@ -0,0 +1,137 @@
+//! Parallax background: patterns drawn behind the tiles of an area, scrolling slower than the camera.
+//!
+//! Layers only depend on the camera, so they need no state and are never collided with.
+
+use crate::{RoomData, Scalar, camera::Camera, utils::{ConstMsg, draw_colours}, wasm4::{BLIT_1BPP, SCREEN_SIZE, blit}};
+
+/// Pattern drawn behind the tiles of an area, scrolling slower than them
+pub struct Layer {
+    /// Which 8x8 cells of a 16x16 block repeated over the whole screen have the sprite, see `room16x16`
+    pub pattern: RoomData,
+    /// `size`x`size` pixels, upper left corner at the cell
+    pub sprite: &'static [u8],
+    pub size: u32,
+    /// Part of the camera movement the layer follows: 0 - fixed on screen, 1 - moves together with the tiles
+    pub speed: f32,
+    /// Colour for `draw_colours`, to set the layer apart from the tiles
+    pub colour: u8,
+}
+
+impl Layer {
+    /// Compile-time check that `sprite` has `size`x`size` pixels
+    pub const fn check(&self, area: &str) {
+        let expected = (self.size * self.size / 8) as usize;
+        if self.sprite.len() != expected {
+            ConstMsg::new("Background layer of area ").s(area).s(" has a sprite of ").n(self.sprite.len())
+                .s(" bytes instead of ").n(expected).s(" for its size").panic();
+        }
+    }
+
+    pub fn draw(&self, cam: &Camera) {
+        draw_colours(self.colour, 0, 0, 0);
+        // where the point the camera looks at is drawn, off the center of the screen while it shakes
+        let center = cam.to_screen(cam.pos);
+        // position of the upper left corner of the screen within the layer
+        let left = (cam.pos.re.to_f32() * self.speed - center.re.to_f32()).floor() as i32;
+        let top = (cam.pos.im.to_f32() * self.speed - center.im.to_f32()).floor() as i32;
+        // sprites larger than a cell may come from cells left of or above the screen
+        let margin = (self.size as i32 + 7) / 8 - 1;
+        let cells = SCREEN_SIZE as i32 / 8 + 1;
+        for cy in top.div_euclid(8) - margin..=top.div_euclid(8) + cells {
+            let row = self.pattern[cy.rem_euclid(16) as usize];
+            for cx in left.div_euclid(8) - margin..=left.div_euclid(8) + cells {
+                if (row >> (cx.rem_euclid(16) * 2)) & 0b11 != 0 {
+                    blit(self.sprite, cx * 8 - left, cy * 8 - top, self.size, self.size, BLIT_1BPP);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{cf32, sprites::{BG_ARCH, BG_PEBBLES}, utils::room16x16, wasm4::{self, get_pixel}};
+
+    const SPARSE: RoomData = room16x16(b"
+        |`               |
+        |                |
+        |        ,       |
+        |                |
+        |   `            |
+        |                |
+        |                |
+        |             X  |
+    ");
+
+    fn screen() -> Vec<u8> {
+        (0..SCREEN_SIZE).flat_map(|y| (0..SCREEN_SIZE).map(move |x| get_pixel(x, y))).collect()
+    }
+
+    fn drawn_at(layer: &Layer, pos: cf32) -> Vec<u8> {
+        wasm4::reset();
+        let mut cam = Camera::new();
+        cam.pos = pos;
+        layer.draw(&cam);
+        screen()
+    }
+
+    #[test]
+    fn scrolls_slower_than_camera() {
+        let _guard = wasm4::exclusive();
+        let layer = Layer { pattern: SPARSE, sprite: &BG_PEBBLES, size: 8, speed: 0.5, colour: 4 };
+        let before = drawn_at(&layer, cf32::new(400.0, 300.0));
+        assert!(before.iter().any(|&c| c == 3));
+        assert!(before.iter().all(|&c| c == 0 || c == 3));
+        // moving the camera by 32 pixels moves the layer by 16
+        let after = drawn_at(&layer, cf32::new(432.0, 300.0));
+        let size = SCREEN_SIZE as usize;
+        for y in 0..size {
+            assert_eq!(before[y * size + 16..(y + 1) * size], after[y * size..(y + 1) * size - 16], "row {}", y);
+        }
+        let after = drawn_at(&layer, cf32::new(400.0, 332.0));
+        assert_eq!(before[16 * size..], after[..(size - 16) * size]);
+    }
+
+    #[test]
+    fn large_sprites_enter_from_the_edges() {
+        let _guard = wasm4::exclusive();
+        let layer = Layer { pattern: SPARSE, sprite: &BG_ARCH, size: 16, speed: 1.0, colour: 4 };
+        // the arch at cell 0, 0 starts 8 pixels left of and above the screen
+        drawn_at(&layer, cf32::new(88.0, 88.0));
+        assert_eq!(get_pixel(0, 7), 3);
+        assert_eq!(get_pixel(7, 7), 3);
+        assert_eq!(get_pixel(0, 0), 0);
+    }
+
+    #[test]
+    fn shakes_with_the_camera() {
+        let _guard = wasm4::exclusive();
+        let layer = Layer { pattern: SPARSE, sprite: &BG_PEBBLES, size: 8, speed: 0.0, colour: 4 };
+        let still = drawn_at(&layer, cf32::new(100.0, 100.0));
+        wasm4::reset();
+        let mut cam = Camera::new();
+        cam.pos = cf32::new(100.0, 100.0);
+        // the first step of the shake is to the right
+        cam.shake(3.0);
+        layer.draw(&cam);
+        let shaken = screen();
+        let size = SCREEN_SIZE as usize;
+        for y in 0..size {
+            assert_eq!(still[y * size..(y + 1) * size - 3], shaken[y * size + 3..(y + 1) * size], "row {}", y);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Background layer of area TEST has a sprite of 8 bytes instead of 32 for its size")]
+    fn sprite_must_match_size() {
+        Layer { pattern: SPARSE, sprite: &BG_PEBBLES, size: 16, speed: 0.5, colour: 4 }.check("TEST");
+    }
+
+    #[test]
+    fn zero_speed_stays_on_screen() {
+        let _guard = wasm4::exclusive();
+        let layer = Layer { pattern: SPARSE, sprite: &BG_ARCH, size: 16, speed: 0.0, colour: 4 };
+        assert_eq!(drawn_at(&layer, cf32::new(100.0, 100.0)), drawn_at(&layer, cf32::new(900.0, 250.0)));
+    }
+}
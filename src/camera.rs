@@ -1,10 +1,85 @@
-use crate::{cf32, player::Player, wasm4::{BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP}};
+use crate::{Real, cf32, player::Player, real, utils::text_error, wasm4::{BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, SCREEN_SIZE}, world::World};
+
+/// How the camera frames a room. Set per room in the level.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    /// Follow the player with inertia, looking ahead
+    Follow,
+    /// Follow the player, but do not show beyond the room and adjacent rooms in this mode
+    Locked,
+    /// Follow the player horizontally, vertically centered on the room
+    Horizontal,
+    /// Show the room in the middle of the screen
+    Fixed,
+}
+
+/// Parse camera modes of the 8x4 rooms of an area, one letter per room between `|` markers:
+/// `F` - follow, `L` - locked, `H` - horizontal, `S` - fixed screen.
+pub const fn camera_modes(s: &'static [u8]) -> [CameraMode; 32] {
+    let mut buf = [CameraMode::Follow; 32];
+    let mut lineidx = 0;
+    let mut column = 0;
+    let mut within_rooms_area = false;
+
+    let mut i = 0;
+    while i < s.len() {
+        let chr = s[i];
+        if chr == b'|' {
+            within_rooms_area = !within_rooms_area;
+            if within_rooms_area && lineidx >= 4 {
+                text_error("Camera modes", lineidx, 0, chr, "there must be exactly 4 lines of rooms");
+            }
+            if !within_rooms_area {
+                if column != 8 {
+                    text_error("Camera modes", lineidx, column, chr, "there must be exactly 8 rooms in each line");
+                }
+                lineidx += 1;
+                column = 0;
+            }
+        } else if within_rooms_area {
+            if column >= 8 {
+                text_error("Camera modes", lineidx, column, chr, "there must be exactly 8 rooms in each line");
+            }
+            buf[lineidx * 8 + column] = match chr {
+                b'F' => CameraMode::Follow,
+                b'L' => CameraMode::Locked,
+                b'H' => CameraMode::Horizontal,
+                b'S' => CameraMode::Fixed,
+                _ => text_error("Camera modes", lineidx, column, chr, "expected F, L, H or S"),
+            };
+            column += 1;
+        }
+        i += 1;
+    }
+    if lineidx != 4 || within_rooms_area {
+        text_error("Camera modes", lineidx, column, b'|', "there must be exactly 4 lines of rooms");
+    }
+    buf
+}
+
+/// Size of a room in pixels
+const ROOM_SIZE: f32 = 16.0 * 8.0;
+/// Part of the way to its target the camera moves each frame in modes other than `Follow`
+const GLIDE: f32 = 0.08;
+/// Part of the shake amplitude left after a frame
+const SHAKE_DECAY: f32 = 0.85;
+/// Part of the zoom punch left after a frame
+const PUNCH_DECAY: f32 = 0.8;
+/// Directions of the view offset in consecutive frames of a shake
+const SHAKE_PATTERN: [(f32, f32); 8] = [(1.0, 0.0), (-0.7, 0.7), (0.0, -1.0), (0.7, 0.7), (-1.0, 0.0), (0.7, -0.7), (0.0, 1.0), (-0.7, -0.7)];
 
 pub struct Camera {
     pub pos: cf32,
     inertia: cf32,
     limit: u8,
     look_down_ctr: u8,
+    /// Pixels
+    shake: f32,
+    shake_step: u8,
+    /// Frames left
+    freeze: u8,
+    /// Relative, `0.1` spreads sprites 10% away from the center of the screen
+    punch: f32,
 }
 
 impl Camera {
@@ -14,10 +89,106 @@ impl Camera {
             inertia: cf32::new(0.0, 0.0),
             limit: 0,
             look_down_ctr: 0,
+            shake: 0.0,
+            shake_step: 0,
+            freeze: 0,
+            punch: 0.0,
         }
     }
 
+    /// Shake the view by up to `amplitude` pixels, decaying over the following frames
+    pub fn shake(&mut self, amplitude: f32) {
+        self.shake = self.shake.max(amplitude);
+    }
+
+    /// Stop gameplay for the given number of frames, to stress a hit
+    pub fn freeze(&mut self, frames: u8) {
+        self.freeze = self.freeze.max(frames);
+    }
+
+    /// Briefly spread sprites away from the center of the screen by `amount` of their distance, as if zooming in
+    pub fn punch(&mut self, amount: f32) {
+        self.punch = self.punch.max(amount);
+    }
+
+    /// Whether gameplay stands still in this frame. Counts down the freeze.
+    pub fn hold(&mut self) -> bool {
+        if self.freeze > 0 {
+            self.freeze -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Where a point of the world is drawn, with effects applied
+    pub fn to_screen(&self, pos: cf32) -> cf32 {
+        let (dx, dy) = SHAKE_PATTERN[self.shake_step as usize % SHAKE_PATTERN.len()];
+        (pos - self.pos) * (1.0 + self.punch) + cf32::new(dx * self.shake, dy * self.shake) + cf32::new(0.5, 0.5) * SCREEN_SIZE as f32
+    }
+
     pub fn update(&mut self, p : &Player, keys: u8) {
+        match World::camera_mode_at(p.my_world_coords()) {
+            CameraMode::Follow => self.follow(p, keys),
+            mode => self.glide(p, mode),
+        }
+        // never show the void beyond the level
+        let (width, height) = World::BOTTOM_RIGHT_TILE;
+        self.pos.re = Camera::clamp_axis(self.pos.re, 0.0, width as f32 * 8.0);
+        self.pos.im = Camera::clamp_axis(self.pos.im, 0.0, height as f32 * 8.0);
+
+        self.shake = if self.shake > 0.5 { self.shake * SHAKE_DECAY } else { 0.0 };
+        self.shake_step = self.shake_step.wrapping_add(1);
+        self.punch = if self.punch > 0.01 { self.punch * PUNCH_DECAY } else { 0.0 };
+    }
+
+    /// Move towards the view of the room the player is in
+    fn glide(&mut self, p : &Player, mode: CameraMode) {
+        let tile = p.my_world_coords();
+        let room = (tile.0 >> 4, tile.1 >> 4);
+        let center = cf32::new((room.0 as f32 + 0.5) * ROOM_SIZE, (room.1 as f32 + 0.5) * ROOM_SIZE);
+        let lookahead = p.pos + p.vel*0.1;
+        let target = match mode {
+            CameraMode::Follow => lookahead,
+            CameraMode::Locked => {
+                let locked = |x: u16, y: u16| World::camera_mode_at((x << 4, y << 4)) == CameraMode::Locked;
+                let (mut left, mut right, mut top, mut bottom) = (room.0, room.0, room.1, room.1);
+                while left > 0 && locked(left - 1, room.1) { left -= 1; }
+                while locked(right + 1, room.1) { right += 1; }
+                while top > 0 && locked(room.0, top - 1) { top -= 1; }
+                while locked(room.0, bottom + 1) { bottom += 1; }
+                let mut target = lookahead;
+                target.re = Camera::clamp_axis(target.re, left as f32 * ROOM_SIZE, (right + 1) as f32 * ROOM_SIZE);
+                target.im = Camera::clamp_axis(target.im, top as f32 * ROOM_SIZE, (bottom + 1) as f32 * ROOM_SIZE);
+                target
+            }
+            CameraMode::Horizontal => {
+                let mut target = lookahead;
+                target.im = center.im;
+                target
+            }
+            CameraMode::Fixed => center,
+        };
+        // glide, so that the view does not jump when crossing into the room
+        self.pos += (target - self.pos) * GLIDE;
+        self.inertia = cf32::new(0.0, 0.0);
+        self.limit = 0;
+    }
+
+    /// Keep the view between `from` and `to` (in pixels) along one axis, or center it there if the span is narrower than the screen
+    fn clamp_axis(x: Real, from: f32, to: f32) -> Real {
+        let half = SCREEN_SIZE as f32 / 2.0;
+        if to - from <= 2.0 * half {
+            real((from + to) / 2.0)
+        } else {
+            // not `clamp`, its panic message would pull float formatting into the cart
+            let (lo, hi) = (from + half, to - half);
+            if x < lo { real(lo) } else if x > hi { real(hi) } else { x }
+        }
+    }
+
+    /// Follow the player with inertia, looking ahead in the direction of movement or where the d-pad points
+    fn follow(&mut self, p : &Player, keys: u8) {
         let mut futurepos = p.pos + p.vel*0.1;
         if p.jump_dir.is_some() {
             futurepos += cf32::new(0.0, -30.0);
@@ -86,3 +257,128 @@ impl Camera {
         self.inertia *= 0.99;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Scalar, UniqueItem};
+
+    #[test]
+    fn notation() {
+        const M: [CameraMode; 32] = camera_modes(b"
+            |FLHSFFFF|
+            |FFFFFFFF|
+            |FFFFFFFF|
+            |FFFFFFFS|
+        ");
+        assert_eq!(M[..4], [CameraMode::Follow, CameraMode::Locked, CameraMode::Horizontal, CameraMode::Fixed]);
+        assert_eq!(M[31], CameraMode::Fixed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Camera modes line 2, column 3, character 'x': expected F, L, H or S")]
+    fn unknown_mode() {
+        camera_modes(b"|FFFFFFFF|\n|FFxFFFFF|\n|FFFFFFFF|\n|FFFFFFFF|");
+    }
+
+    #[test]
+    fn locked_rooms_keep_view_inside() {
+        let mut player = Player::new();
+        player.respawn(UniqueItem::PlayerStart.get_pos());
+        let room = (player.my_world_coords().0 >> 4, player.my_world_coords().1 >> 4);
+        assert_eq!(World::camera_mode_at(player.my_world_coords()), CameraMode::Locked);
+        let mut camera = Camera::new();
+        camera.pos = player.pos;
+        for _ in 0..200 {
+            camera.update(&player, 0);
+        }
+        // the locked block is a single row of rooms, lower than the screen
+        assert!((camera.pos.im.to_f32() - (room.1 as f32 + 0.5) * ROOM_SIZE).abs() < 0.5, "{:?}", camera.pos);
+        // and spans rooms 5 to 7 of the area
+        let half = SCREEN_SIZE as f32 / 2.0;
+        assert!(camera.pos.re.to_f32() <= 8.0 * ROOM_SIZE - half + 0.5, "{:?}", camera.pos);
+        assert!(camera.pos.re.to_f32() >= 5.0 * ROOM_SIZE + half - 0.5, "{:?}", camera.pos);
+    }
+
+    #[test]
+    fn glides_when_entering_room() {
+        let mut player = Player::new();
+        player.respawn(UniqueItem::CheckpointCorridor.get_pos());
+        assert_eq!(World::camera_mode_at(player.my_world_coords()), CameraMode::Horizontal);
+        let center = ((player.my_world_coords().1 >> 4) as f32 + 0.5) * ROOM_SIZE;
+        let mut camera = Camera::new();
+        // as if the view was following the player in the room above
+        camera.pos = player.pos - cf32::new(0.0, 60.0);
+        let mut prev = camera.pos;
+        for _ in 0..200 {
+            camera.update(&player, 0);
+            assert!((camera.pos - prev).norm() < 8.0, "{:?} {:?}", prev, camera.pos);
+            prev = camera.pos;
+        }
+        assert!((camera.pos.im.to_f32() - center).abs() < 0.5, "{:?}", camera.pos);
+        assert!((camera.pos.re - player.pos.re).abs() < 0.5, "{:?}", camera.pos);
+    }
+
+    #[test]
+    fn view_stays_within_level() {
+        let half = SCREEN_SIZE as f32 / 2.0;
+        let (width, height) = World::BOTTOM_RIGHT_TILE;
+        for corner in [(1, 1), (width - 2, height - 2)] {
+            let mut player = Player::new();
+            player.respawn(corner);
+            let mut camera = Camera::new();
+            camera.pos = player.pos;
+            for _ in 0..300 {
+                camera.update(&player, BUTTON_DOWN | BUTTON_LEFT | BUTTON_UP);
+                let (x, y) = (camera.pos.re.to_f32(), camera.pos.im.to_f32());
+                assert!(x >= half && x <= width as f32 * 8.0 - half, "{:?} {:?}", corner, camera.pos);
+                assert!(y >= half && y <= height as f32 * 8.0 - half, "{:?} {:?}", corner, camera.pos);
+            }
+        }
+    }
+
+    #[test]
+    fn effects_wear_off_without_moving_the_camera() {
+        let mut player = Player::new();
+        player.respawn(UniqueItem::CheckpointCorridor.get_pos());
+        let mut camera = Camera::new();
+        camera.pos = player.pos - cf32::new(20.0, 20.0);
+        let mut plain = Camera::new();
+        plain.pos = camera.pos;
+
+        camera.shake(3.0);
+        camera.punch(0.1);
+        let beside = player.pos + cf32::new(10.0, 0.0);
+        let offset = camera.to_screen(beside) - camera.to_screen(player.pos);
+        assert!((offset - cf32::new(11.0, 0.0)).norm() < 0.01, "{:?}", offset);
+        let mut shaken = false;
+        for _ in 0..60 {
+            camera.update(&player, BUTTON_LEFT);
+            plain.update(&player, BUTTON_LEFT);
+            assert!((camera.pos - plain.pos).norm() < 0.01, "{:?} {:?}", plain.pos, camera.pos);
+            let shift = (camera.to_screen(player.pos) - plain.to_screen(player.pos)).norm();
+            assert!(shift <= 3.5, "{}", shift);
+            shaken |= shift > 1.0;
+        }
+        assert!(shaken);
+        assert!((camera.to_screen(beside) - plain.to_screen(beside)).norm() < 0.01);
+    }
+
+    #[test]
+    fn freeze_counts_down() {
+        let mut camera = Camera::new();
+        assert!(!camera.hold());
+        camera.freeze(3);
+        camera.freeze(2);
+        assert!(camera.hold() && camera.hold() && camera.hold());
+        assert!(!camera.hold());
+    }
+
+    #[test]
+    fn small_level_is_centered() {
+        assert_eq!(Camera::clamp_axis(real(10.0), 0.0, 96.0), real(48.0));
+        assert_eq!(Camera::clamp_axis(real(10.0), 0.0, 400.0), real(80.0));
+        assert_eq!(Camera::clamp_axis(real(390.0), 0.0, 400.0), real(320.0));
+        assert_eq!(Camera::clamp_axis(real(200.0), 0.0, 400.0), real(200.0));
+    }
+}
@@ -0,0 +1,219 @@
+//! Enemies: creatures placed from the level text like unique items, hurting the player on contact.
+
+use crate::{Real, Scalar, real, MAX_ENEMIES_PER_AREA, camera::Camera, cf32, level, sprites, TilePos, tiles::{CollisionSegment, TileType}, utils::draw_colours, wasm4::{BLIT_1BPP, BLIT_FLIP_X, SCREEN_SIZE, blit}, world::World};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnemyKind {
+    /// Patrols left and right, turning around at walls and ledges
+    Walker,
+    /// Hangs from the ceiling, falls when the player passes below and then climbs back
+    Dropper,
+}
+
+#[derive(Clone, Copy)]
+pub struct EnemyPosition {
+    pub kind: EnemyKind,
+    pub pos: TilePos,
+}
+
+/// Pixels per frame
+const WALKER_SPEED: Real = real(0.3);
+/// How far below a dropper (in tiles) the player is noticed
+const DROPPER_SIGHT: u16 = 10;
+const DROPPER_GRAVITY: Real = real(0.1);
+const DROPPER_MAX_SPEED: Real = real(3.0);
+const DROPPER_CLIMB_SPEED: Real = real(0.25);
+/// Frames to lie on the floor before climbing back
+const DROPPER_REST: u8 = 60;
+
+static ENEMY_SHAPE: [CollisionSegment; 4] = [
+    CollisionSegment{rp1: cf32::new(-2.5,  -2.5), rp2: cf32::new(2.5,  -2.5), rad:1.0, el: 0.0},
+    CollisionSegment{rp1: cf32::new( 2.5,  -2.5), rp2: cf32::new(2.5,   2.5), rad:1.0, el: 0.0},
+    CollisionSegment{rp1: cf32::new( 2.5,   2.5), rp2: cf32::new(-2.5,  2.5), rad:1.0, el: 0.0},
+    CollisionSegment{rp1: cf32::new(-2.5,   2.5), rp2: cf32::new(-2.5, -2.5), rad:1.0, el: 0.0},
+];
+
+const MAX_ENEMIES: usize = MAX_ENEMIES_PER_AREA * level::AREAS.len();
+
+#[derive(Clone, Copy)]
+struct EnemyState {
+    /// Relative to the placement position, in pixels
+    offset: cf32,
+    /// Pixels per frame
+    vel: cf32,
+    /// Frames left to rest
+    rest: u8,
+}
+
+impl EnemyState {
+    const INITIAL: EnemyState = EnemyState {
+        offset: cf32::new(0.0, 0.0),
+        vel: cf32::new(0.0, 0.0),
+        rest: 0,
+    };
+}
+
+fn solid_at(pos: cf32) -> bool {
+    !World::get_tile(World::to_world_coords(pos)).collision_configuration().is_empty()
+}
+
+#[derive(Clone)]
+pub struct Enemies {
+    /// Indexed the same way as `World::enemies`
+    states: [EnemyState; MAX_ENEMIES],
+}
+
+impl Enemies {
+    pub const fn new() -> Enemies {
+        Enemies {
+            states: [EnemyState::INITIAL; MAX_ENEMIES],
+        }
+    }
+
+    /// Put all enemies back to where they were placed
+    pub fn reset(&mut self) {
+        self.states = [EnemyState::INITIAL; MAX_ENEMIES];
+    }
+
+    fn position(&self, i: usize, enemy: &EnemyPosition) -> cf32 {
+        World::from_world_coords(enemy.pos) + self.states[i].offset
+    }
+
+    /// Advance enemies by one frame. Droppers react to the player at `player_pos`.
+    pub fn tick(&mut self, player_pos: cf32) {
+        let player_tile = World::to_world_coords(player_pos);
+        for (i, enemy) in World::enemies().iter().enumerate() {
+            let enemy = match enemy {
+                Some(x) => x,
+                None => continue,
+            };
+            let pos = self.position(i, enemy);
+            let state = &mut self.states[i];
+            match enemy.kind {
+                EnemyKind::Walker => {
+                    if state.vel.re == 0.0 {
+                        state.vel.re = -WALKER_SPEED;
+                    }
+                    let ahead = pos + cf32::new(5.0, 0.0) * state.vel.re.signum();
+                    if solid_at(ahead) || !solid_at(ahead + cf32::new(0.0, 8.0)) {
+                        state.vel.re = -state.vel.re;
+                    } else {
+                        state.offset += state.vel;
+                    }
+                }
+                EnemyKind::Dropper => {
+                    if state.rest > 0 {
+                        state.rest -= 1;
+                        if state.rest == 0 {
+                            state.vel.im = -DROPPER_CLIMB_SPEED;
+                        }
+                    } else if state.vel.im > 0.0 {
+                        state.vel.im = (state.vel.im + DROPPER_GRAVITY).min(DROPPER_MAX_SPEED);
+                        if solid_at(pos + cf32::new(0.0, 4.0) + cf32::new(0.0, 1.0) * state.vel.im) {
+                            state.vel.im = real(0.0);
+                            state.rest = DROPPER_REST;
+                        } else {
+                            state.offset += state.vel;
+                        }
+                    } else if state.vel.im < 0.0 {
+                        state.offset += state.vel;
+                        if state.offset.im <= 0.0 {
+                            *state = EnemyState::INITIAL;
+                        }
+                    } else {
+                        let dx = (player_tile.0 as i32 - enemy.pos.0 as i32).abs();
+                        let dy = player_tile.1 as i32 - enemy.pos.1 as i32;
+                        if dx <= 1 && dy > 0 && dy <= DROPPER_SIGHT as i32 {
+                            state.vel.im = DROPPER_GRAVITY;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Position of an enemy overlapping a circle of `radius` around `pos`
+    pub fn touching(&self, pos: cf32, radius: Real) -> Option<cf32> {
+        for (i, enemy) in World::enemies().iter().enumerate() {
+            if let Some(enemy) = enemy {
+                let center = self.position(i, enemy);
+                if CollisionSegment::touches(&ENEMY_SHAPE, center, pos, radius) {
+                    return Some(center);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, global_frame: u8, cam: &Camera) {
+        draw_colours(4, 0, 0, 0);
+        for (i, enemy) in World::enemies().iter().enumerate() {
+            let enemy = match enemy {
+                Some(x) => x,
+                None => continue,
+            };
+            let upperleft = cam.to_screen(self.position(i, enemy)) - cf32::new(4.0, 4.0);
+            if upperleft.re < -8.0 || upperleft.im < -8.0 || upperleft.re > SCREEN_SIZE as f32 || upperleft.im > SCREEN_SIZE as f32 {
+                continue;
+            }
+            let (sprite, flags) = match enemy.kind {
+                EnemyKind::Walker if global_frame & 0x10 != 0 => (&sprites::WALKER, BLIT_1BPP | BLIT_FLIP_X),
+                EnemyKind::Walker => (&sprites::WALKER, BLIT_1BPP),
+                EnemyKind::Dropper => (&sprites::DROPPER, BLIT_1BPP),
+            };
+            blit(sprite, upperleft.re.to_f32() as i32, upperleft.im.to_f32() as i32, 8, 8, flags);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn find(kind: EnemyKind) -> (usize, EnemyPosition) {
+        World::enemies().iter().enumerate()
+            .find_map(|(i, e)| e.filter(|e| e.kind == kind).map(|e| (i, e)))
+            .unwrap()
+    }
+
+    #[test]
+    fn walker_patrols_without_falling() {
+        let (i, walker) = find(EnemyKind::Walker);
+        let mut enemies = Enemies::new();
+        let start = enemies.position(i, &walker);
+        let (mut min, mut max) = (start.re, start.re);
+        for _ in 0..2000 {
+            enemies.tick(cf32::new(0.0, 0.0));
+            let pos = enemies.position(i, &walker);
+            assert_eq!(pos.im, start.im);
+            assert!(!solid_at(pos));
+            min = min.min(pos.re);
+            max = max.max(pos.re);
+        }
+        // turned around at both ends
+        assert!(min < start.re - 8.0 && max > start.re + 8.0);
+    }
+
+    #[test]
+    fn dropper_falls_on_player_and_returns() {
+        let (i, dropper) = find(EnemyKind::Dropper);
+        let mut enemies = Enemies::new();
+        let home = enemies.position(i, &dropper);
+
+        enemies.tick(home + cf32::new(30.0, 40.0));
+        enemies.tick(home + cf32::new(30.0, 40.0));
+        assert_eq!(enemies.position(i, &dropper), home);
+
+        let below = home + cf32::new(0.0, 40.0);
+        for _ in 0..60 {
+            enemies.tick(below);
+        }
+        assert!(enemies.position(i, &dropper).im > home.im + 16.0);
+        assert!(enemies.touching(enemies.position(i, &dropper) + cf32::new(5.0, 0.0), real(3.0)).is_some());
+
+        for _ in 0..1000 {
+            enemies.tick(cf32::new(0.0, 0.0));
+        }
+        assert_eq!(enemies.position(i, &dropper), home);
+    }
+}
@@ -0,0 +1,373 @@
+//! Fixed-point numbers, used instead of `f32` by the whole simulation with the `fixed-point` feature.
+//!
+//! Integer arithmetic gives the same results with any toolchain and platform, so recorded inputs always replay the same.
+//! Both types mix with `f32` in arithmetic and comparisons, so the same game code compiles either way.
+
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+const FRAC_BITS: u32 = 16;
+
+/// Signed number with 16 integer and 16 fractional bits. Overflows saturate, division by zero gives the largest value.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRAC_BITS);
+    pub const MAX: Fixed = Fixed(i32::MAX);
+    pub const MIN: Fixed = Fixed(i32::MIN);
+
+    /// Rounds towards zero. NaN becomes zero.
+    pub const fn from_f32(x: f32) -> Fixed {
+        Fixed((x * (1 << FRAC_BITS) as f32) as i32)
+    }
+
+    pub const fn to_raw(self) -> i32 {
+        self.0
+    }
+
+    pub const fn from_raw(raw: i32) -> Fixed {
+        Fixed(raw)
+    }
+
+    const fn saturate(raw: i64) -> Fixed {
+        if raw > i32::MAX as i64 {
+            Fixed::MAX
+        } else if raw < i32::MIN as i64 {
+            Fixed::MIN
+        } else {
+            Fixed(raw as i32)
+        }
+    }
+
+    pub fn abs(self) -> Fixed {
+        Fixed(self.0.saturating_abs())
+    }
+
+    pub fn signum(self) -> Fixed {
+        if self.0 < 0 { -Fixed::ONE } else { Fixed::ONE }
+    }
+
+    pub fn min(self, other: impl Into<Fixed>) -> Fixed {
+        Ord::min(self, other.into())
+    }
+
+    pub fn max(self, other: impl Into<Fixed>) -> Fixed {
+        Ord::max(self, other.into())
+    }
+
+    pub fn clamp(self, min: impl Into<Fixed>, max: impl Into<Fixed>) -> Fixed {
+        Ord::clamp(self, min.into(), max.into())
+    }
+
+    /// Of the absolute value
+    pub fn sqrt(self) -> Fixed {
+        Fixed::saturate(((self.0.unsigned_abs() as u64) << FRAC_BITS).isqrt() as i64)
+    }
+}
+
+impl From<f32> for Fixed {
+    fn from(x: f32) -> Fixed {
+        Fixed::from_f32(x)
+    }
+}
+
+impl core::fmt::Display for Fixed {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        self.to_f32().fmt(f)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, rhs: Fixed) -> Fixed {
+        Fixed::saturate((self.0 as i64 * rhs.0 as i64) >> FRAC_BITS)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+    fn div(self, rhs: Fixed) -> Fixed {
+        match (self.0, rhs.0) {
+            (0, _) => Fixed::ZERO,
+            (a, 0) if a > 0 => Fixed::MAX,
+            (_, 0) => Fixed::MIN,
+            (a, b) => Fixed::saturate(((a as i64) << FRAC_BITS) / b as i64),
+        }
+    }
+}
+
+/// Operators between fixed-point values and `f32`, in both orders, with `f32` converted first
+macro_rules! mixed_ops {
+    ($($op:ident $method:ident $assign_op:ident $assign_method:ident),*) => {$(
+        impl $op<f32> for Fixed {
+            type Output = Fixed;
+            fn $method(self, rhs: f32) -> Fixed {
+                $op::$method(self, Fixed::from_f32(rhs))
+            }
+        }
+        impl $op<Fixed> for f32 {
+            type Output = Fixed;
+            fn $method(self, rhs: Fixed) -> Fixed {
+                $op::$method(Fixed::from_f32(self), rhs)
+            }
+        }
+        impl<T: Into<Fixed>> $assign_op<T> for Fixed {
+            fn $assign_method(&mut self, rhs: T) {
+                *self = $op::$method(*self, rhs.into());
+            }
+        }
+    )*};
+}
+mixed_ops!(Add add AddAssign add_assign, Sub sub SubAssign sub_assign, Mul mul MulAssign mul_assign, Div div DivAssign div_assign);
+
+impl PartialEq<f32> for Fixed {
+    fn eq(&self, other: &f32) -> bool {
+        *self == Fixed::from_f32(*other)
+    }
+}
+
+impl PartialEq<Fixed> for f32 {
+    fn eq(&self, other: &Fixed) -> bool {
+        Fixed::from_f32(*self) == *other
+    }
+}
+
+impl PartialOrd<f32> for Fixed {
+    fn partial_cmp(&self, other: &f32) -> Option<core::cmp::Ordering> {
+        self.partial_cmp(&Fixed::from_f32(*other))
+    }
+}
+
+impl PartialOrd<Fixed> for f32 {
+    fn partial_cmp(&self, other: &Fixed) -> Option<core::cmp::Ordering> {
+        Fixed::from_f32(*self).partial_cmp(other)
+    }
+}
+
+/// Complex number with fixed-point components, providing the subset of `Complex32` the game uses
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct CFixed {
+    pub re: Fixed,
+    pub im: Fixed,
+}
+
+impl CFixed {
+    pub const fn new(re: f32, im: f32) -> CFixed {
+        CFixed { re: Fixed::from_f32(re), im: Fixed::from_f32(im) }
+    }
+
+    pub fn norm_sqr(self) -> Fixed {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Computed from the squares in full precision, so it does not overflow for long vectors
+    pub fn norm(self) -> Fixed {
+        let (re, im) = (self.re.0 as i64, self.im.0 as i64);
+        Fixed::saturate(((re * re + im * im) as u64).isqrt() as i64)
+    }
+
+    pub fn scale(self, t: impl Into<Fixed>) -> CFixed {
+        let t = t.into();
+        CFixed { re: self.re * t, im: self.im * t }
+    }
+
+    pub fn unscale(self, t: impl Into<Fixed>) -> CFixed {
+        let t = t.into();
+        CFixed { re: self.re / t, im: self.im / t }
+    }
+
+    pub fn conj(self) -> CFixed {
+        CFixed { re: self.re, im: -self.im }
+    }
+
+    /// Like for `Complex32`, false if a component is zero. Also false if a component has overflown.
+    pub fn is_normal(self) -> bool {
+        [self.re, self.im].iter().all(|&x| x != Fixed::ZERO && x != Fixed::MAX && x != Fixed::MIN)
+    }
+}
+
+impl core::fmt::Display for CFixed {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}{:+}i", self.re, self.im.to_f32())
+    }
+}
+
+impl Neg for CFixed {
+    type Output = CFixed;
+    fn neg(self) -> CFixed {
+        CFixed { re: -self.re, im: -self.im }
+    }
+}
+
+impl Add for CFixed {
+    type Output = CFixed;
+    fn add(self, rhs: CFixed) -> CFixed {
+        CFixed { re: self.re + rhs.re, im: self.im + rhs.im }
+    }
+}
+
+impl Sub for CFixed {
+    type Output = CFixed;
+    fn sub(self, rhs: CFixed) -> CFixed {
+        CFixed { re: self.re - rhs.re, im: self.im - rhs.im }
+    }
+}
+
+impl AddAssign for CFixed {
+    fn add_assign(&mut self, rhs: CFixed) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for CFixed {
+    fn sub_assign(&mut self, rhs: CFixed) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul for CFixed {
+    type Output = CFixed;
+    fn mul(self, rhs: CFixed) -> CFixed {
+        CFixed {
+            re: self.re * rhs.re - self.im * rhs.im,
+            im: self.re * rhs.im + self.im * rhs.re,
+        }
+    }
+}
+
+/// Used to project one vector onto another: the real part of `a / b` is the position of `a` along `b`.
+/// Computed in full precision, as the squared norm of `b` can be too large or too small for `Fixed`.
+impl Div for CFixed {
+    type Output = CFixed;
+    fn div(self, rhs: CFixed) -> CFixed {
+        let (a, b, c, d) = (self.re.0 as i128, self.im.0 as i128, rhs.re.0 as i128, rhs.im.0 as i128);
+        let denom = c * c + d * d;
+        let component = |num: i128| {
+            if denom == 0 {
+                return Fixed::ZERO;
+            }
+            let x = (num << FRAC_BITS) / denom;
+            Fixed::saturate(x.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+        };
+        CFixed { re: component(a * c + b * d), im: component(b * c - a * d) }
+    }
+}
+
+/// Multiplication and division by a real number, like `Complex32` has with `f32`
+macro_rules! scalar_ops {
+    ($($t:ty),*) => {$(
+        impl Mul<$t> for CFixed {
+            type Output = CFixed;
+            fn mul(self, rhs: $t) -> CFixed {
+                self.scale(rhs)
+            }
+        }
+        impl Mul<CFixed> for $t {
+            type Output = CFixed;
+            fn mul(self, rhs: CFixed) -> CFixed {
+                rhs.scale(self)
+            }
+        }
+        impl Div<$t> for CFixed {
+            type Output = CFixed;
+            fn div(self, rhs: $t) -> CFixed {
+                self.unscale(rhs)
+            }
+        }
+        impl MulAssign<$t> for CFixed {
+            fn mul_assign(&mut self, rhs: $t) {
+                *self = self.scale(rhs);
+            }
+        }
+    )*};
+}
+scalar_ops!(Fixed, f32);
+
+/// Type of components of `cf32`
+#[cfg(not(feature = "fixed-point"))]
+pub type Real = f32;
+#[cfg(feature = "fixed-point")]
+pub type Real = Fixed;
+
+/// Convert a constant to `Real`
+#[cfg(not(feature = "fixed-point"))]
+pub const fn real(x: f32) -> Real {
+    x
+}
+#[cfg(feature = "fixed-point")]
+pub const fn real(x: f32) -> Real {
+    Fixed::from_f32(x)
+}
+
+/// Components of `cf32`, whichever type they are
+pub trait Scalar: Copy {
+    fn to_f32(self) -> f32;
+}
+
+impl Scalar for f32 {
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl Scalar for Fixed {
+    fn to_f32(self) -> f32 {
+        self.0 as f32 / (1 << FRAC_BITS) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let (a, b) = (Fixed::from_f32(2.5), Fixed::from_f32(-0.25));
+        assert_eq!(a + b, 2.25);
+        assert_eq!(a * b, -0.625);
+        assert_eq!(a / b, -10.0);
+        assert_eq!(1.0 - a, -1.5);
+        assert!(b < 0.0 && -1.0 < b);
+        assert_eq!(Fixed::from_f32(20000.0) * 4.0, Fixed::MAX);
+        assert_eq!(a / 0.0, Fixed::MAX);
+        assert_eq!(Fixed::from_f32(6.25).sqrt(), 2.5);
+    }
+
+    #[test]
+    fn complex() {
+        let v = CFixed::new(3.0, -4.0);
+        assert_eq!(v.norm(), 5.0);
+        // larger than `Fixed` can hold when squared
+        assert_eq!(CFixed::new(600.0, 800.0).norm(), 1000.0);
+        assert_eq!(v.unscale(v.norm()), CFixed::new(0.6, -0.8));
+        assert_eq!(2.0 * v - v, v);
+        assert_eq!(v * CFixed::new(0.0, 1.0), CFixed::new(4.0, 3.0));
+
+        // projection onto a segment
+        let along = CFixed::new(1.0, 1.0) / CFixed::new(4.0, 0.0);
+        assert_eq!(along, CFixed::new(0.25, 0.25));
+        assert_eq!(CFixed::new(1.0, 1.0) / CFixed::new(0.0, 0.0), CFixed::default());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::utils::{ll_char_descriptions, room16x16};
-use crate::{Area, AreaSource, RoomData, UniqueItemPositions, tile_type, tile_types_mapping, unique_items_mapping};
+use crate::camera::camera_modes;
+use crate::background::Layer;
+use crate::{Area, AreaSource, RoomData, UniqueItemPositions, enemies_mapping, music, platforms_mapping, sprites, tile_type, tile_types_mapping, unique_items_mapping};
 
 
 const FIRST_MAP: RoomData = room16x16( b"
@@ -13,9 +15,53 @@ const FIRST_MAP: RoomData = room16x16( b"
 |XXXXXX,XXXXXXXXX|
 ");
 
+const CAVE_BACKGROUND: [Layer; 1] = [Layer {
+    pattern: room16x16(b"
+|  `        ,    |
+|       X        |
+|            `   |
+| ,    `         |
+|          ,   X |
+|    X           |
+|  `         ,   |
+|        ,       |
+"),
+    sprite: &sprites::BG_PEBBLES,
+    size: 8,
+    speed: 0.5,
+    colour: 4,
+}];
+
+const CORRIDOR_BACKGROUND: [Layer; 1] = [Layer {
+    pattern: room16x16(b"
+|                |
+|                |
+|  `       `     |
+|                |
+|                |
+|                |
+|                |
+|                |
+"),
+    sprite: &sprites::BG_ARCH,
+    size: 16,
+    speed: 0.25,
+    colour: 4,
+}];
+
 pub const AREA1: (Area, UniqueItemPositions) = Area::build(AreaSource {
+    name: "AREA1",
     empty_tile_style: tile_type!(EmptyTile),
     solid_tile_style: tile_type!(UsualArea1Tile),
+    music: Some(&music::CAVE),
+    background: &CAVE_BACKGROUND,
+    // One letter per room: `F` follow, `L` locked to the block of adjacent `L` rooms, `H` horizontal only, `S` fixed screen
+    camera: camera_modes(b"
+|FFFFFFFF|
+|FFFFFFFF|
+|FFFFFLLL|
+|FFFFFFFF|
+"),
     // First char of triplet is identifier. Second one is upper cell type, third one is lower cell type.
     // The same character is also used in tile types mapping and unique items mapping
     //    `X` means solid tile,
@@ -24,9 +70,11 @@ pub const AREA1: (Area, UniqueItemPositions) = Area::build(AreaSource {
     //    `B` ...               B, ...
     //        Same character cannot have both A and B in it. Individual room cannot assign different tile mappings to A or B.
     //    `!` - position of a unique item. In area itself it is an empty tile.
-    char_lookup: ll_char_descriptions::<9>(b"s!. J.A jAX l.B LBX S!. i.!  M!. G.!"),
-    tile_lookup: tile_types_mapping![(JumpyTile J j) (Ladder1Tile L l)],
-    item_lookup: unique_items_mapping![(PlayerStart s) (PlayerStart! S) (InfoWelcome i) (FeatureSmallSize M) (CrateLog G)],
+    char_lookup: ll_char_descriptions::<17>(b"s!. J.A jAX l.B LBX S!. i.!  M!. G.! p!. v.A d!. k.! D.! ~AA gAA t.A"),
+    tile_lookup: tile_types_mapping![(JumpyTile J j) (Ladder1Tile L l) (SpikesTile v) (WaterTile b'~') (LowGravityTile g) (TorchTile t)],
+    item_lookup: unique_items_mapping![(PlayerStart s) (PlayerStart! S) (CheckpointCave k) (InfoWelcome i) (FeatureSmallSize M) (FeatureDoubleJump D) (CrateLog G)],
+    platform_lookup: platforms_mapping![(LIFT p)],
+    enemy_lookup: enemies_mapping![(Dropper d)],
     cells: b"                                                                                                       <
 |` ```           ` ```           ` ```           ` ```           ` ```           ` ```           ` ```           ` ```           |
 |        `               `               `               `               `               `               `               `       |
@@ -40,15 +88,15 @@ pub const AREA1: (Area, UniqueItemPositions) = Area::build(AreaSource {
 |        `               `        jj    ``               `X  X           `     XXX           ,,,XX                    XXXXXXXXXXX|
 |XXXX       ,    XXXX       ,    XXXX       ,    XXXX     X  X   XXXX       ,  XXXXXX  M                              XXXXXXXXXXX|
 |XXXX            XXXX            XXXX            XXXX     X  X   XXXX    XXXXXXXXXXXX  X           XXX                XXXXXXXXXXX|
-|X              XX              XX              XX        X  X  XX       XXXXXXXXXXX`  X                              XXXXXXXXXXX|
-|X   ,``  `,    XX   ,``  `,    XX   ,`               ``  X  X  XX   ,`` XXXXXXXXXX   jX       XXX                    XXXXXXXXXXX|
-|X ,`           XX ,`           XX ,`                     X  X  XX ,`    XXXXXXXXXX  XXX                              XXXXXXXXXXX|
+|X              XX              XX              XX        X  X  XX       XXXXXXXXXXX`  X            d                 XXXXXXXXXXX|
+|X   ,``  `,    XX   ,``  `,    XX   ,`               ``  X  X  XX   ,`` XXXXXXXXXX   jX       XXX       ggggggg      XXXXXXXXXXX|
+|X ,`           XX ,`           XX ,`                     X  X  XX ,`    XXXXXXXXXX  XXX                 ggggggg      XXXXXXXXXXX|
 |XXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXX,                 X,XXX  X XXXXXXXX,XXXXXXXXXXX    X    XXX                       XXXXXXXXXXX|
 |` ```           ` ```           XXX`            ` ```    X  X   ` ```   XXXXXXXXXXXX  X                              XXXXXXXXXXX|
-|        `               `       XXXXLl                   X  X           XXXXXXXXXX    X  XXX                         XXXXXXXXXXX|
-|XXXX       ,    XXXX       ,    XXXXXXLl                 X  X     G            `X  ,XXX                          i s XXXXXXXXXXX|
+|        `               `       XXXXLl                   X  X           XXXXXXXXXX    X  XXX       p                            |
+|XXXX       ,    XXXX       ,    XXXXXXLl                 X  X     G            `X  ,XXX        k        vv   D   i s   ~~~~~~~~ |
 |XXXX            XXXX            XXXXXXXXLl                      XXXX    XXXXXX     XXXX XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
-|X              XX              XX   XXXXXXLl                   XX       XXXXXXXXXX XXXX XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|X    t   t     XX              XX   XXXXXXLl                   XX       XXXXXXXXXX XXXX XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
 |X   ,``  `,    XX   ,``  `,    XX   XXXXXXXXXLl          jjj   XX   ,`` XXXXXXXXX  XXXX XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
 |X ,`           XX ,`           XX ,`XXXXXXXXXXXXXXl            XX ,`    XXXXXXXXX XXXXX XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
 |XXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXX XXXXX XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
@@ -62,3 +110,64 @@ pub const AREA1: (Area, UniqueItemPositions) = Area::build(AreaSource {
 |XXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXXXXXXXX,XXXXXXXXX|
 ",
 });
+
+pub const AREA2: (Area, UniqueItemPositions) = Area::build(AreaSource {
+    name: "AREA2",
+    empty_tile_style: tile_type!(EmptyTile),
+    solid_tile_style: tile_type!(UsualArea1Tile),
+    music: Some(&music::CORRIDOR),
+    background: &CORRIDOR_BACKGROUND,
+    camera: camera_modes(b"
+|FFFFFFFF|
+|FFFFFFFF|
+|HHHHFFFF|
+|FFFFFFFF|
+"),
+    char_lookup: ll_char_descriptions::<12>(b"e.! J.A w.! k.! W.! P.! a.A b.B r.B =B. >AX <BX"),
+    tile_lookup: tile_types_mapping![(JumpyTile J) (Slope22UpLowerTile a) (Slope22UpUpperTile b) (RoundCornerRTile r) (OneWayTile b'=') (ConveyorRightTile b'>') (ConveyorLeftTile b'<')],
+    item_lookup: unique_items_mapping![(CheckpointCorridor k) (FeatureWallCling W) (FeatureGroundPound P) (CrateSerde e)],
+    platform_lookup: [],
+    enemy_lookup: enemies_mapping![(Walker w)],
+    cells: b"                                                                                                       <
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|    ````            ``              ```                     XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|                                                            XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|          k               ab,,,,r   W                       XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XX>>>>>>XXXX<<<<XXXXXXXXXXXXXXXXXXXXXXXX        ========    XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXX``                                      XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXX                                        XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXX    e   P   w         JJJJJJJJJJJJJJJJ  XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+|XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX|
+",
+});
+
+type BuiltArea = (Area, UniqueItemPositions);
+
+/// All areas of the level together with their positions on the area grid
+pub const AREAS: [(&BuiltArea, (u16, u16)); 2] = [
+    (&AREA1, (0, 0)),
+    (&AREA2, (1, 0)),
+];
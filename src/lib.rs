@@ -1,6 +1,5 @@
-#![allow(unconditional_panic)]
-#![allow(clippy::no_effect)]
 #![allow(clippy::identity_op)]
+#![cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
 
 //! Glossary:
 //!
@@ -13,8 +12,8 @@
 //! * Custom cell - Tile for which room metadata mapping should be used to determine the actual cell type.
 //!          low-level custom cell types may map to specific high-level tile types on room granularity while
 //!          empty and solid cells maps to specific high-level tile types on area granularity
-//! * Level - Set of all tiles in the game
-//! * Area - a 8x4 block of rooms - one "unit of compilation" of the level
+//! * Level - Set of all tiles in the game. Consists of areas placed on a grid.
+//! * Area - a 8x4 block of rooms - one "unit of compilation" of the level. Has its own position on the area grid.
 //! * Room - a 16x16 block of tiles
 //! * Special item, unique item, item - One-of-a-kind game objects that should be placed on the level exactly once.
 //!          Although there can be only one of each item, you can temporarily override its position using special `!` mode of mapping.
@@ -25,6 +24,10 @@
 
 // TODO: use `gridly`?
 
+#[cfg(target_arch = "wasm32")]
+mod wasm4;
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "wasm4_host.rs"]
 mod wasm4;
 use mapview::MapViewer;
 use tiles::TileTypeEnum;
@@ -39,15 +42,37 @@ mod tiles;
 mod world;
 mod unique_items;
 mod mapview;
-
-use camera::Camera;
+mod save;
+mod sound;
+mod music;
+mod platforms;
+mod enemies;
+mod abilities;
+mod background;
+mod fixed;
+pub mod replay;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod reachability;
+
+use camera::{Camera, CameraMode};
+use background::Layer;
 use world::World;
 use player::Player;
+use save::SaveData;
+use sound::{Sfx, Sounds};
+use music::Track;
+use platforms::{PlatformKind, PlatformPosition};
+use enemies::{EnemyKind, EnemyPosition};
 
+#[cfg(not(feature = "fixed-point"))]
 use num_complex::Complex32 as cf32;
+#[cfg(feature = "fixed-point")]
+use fixed::CFixed as cf32;
+use fixed::{Real, Scalar, real};
 
 pub use unique_items::UniqueItem;
 pub use unique_items::TouchedUniqueItems;
+pub use unique_items::VisitedRooms;
 
 type RoomData = [u32; 16];
 
@@ -57,6 +82,7 @@ pub struct RoomMetadata {
     block_type_x: Option<TileTypeEnum>,
     block_type_a: Option<TileTypeEnum>,
     block_type_b: Option<TileTypeEnum>,
+    camera: CameraMode,
 }
 
 // 8x4 block of rooms
@@ -64,29 +90,62 @@ type RoomBlock = [RoomData; 32];
 
 pub type TilePos = (u16, u16);
 
+/// Size of an area in tiles
+pub const AREA_SIZE: TilePos = (16*8, 16*4);
+
 pub struct Level {
-    the_area: Area,
+    areas: [PlacedArea; level::AREAS.len()],
+    /// Size of the bounding box of all areas, in areas
+    size_in_areas: (u16, u16),
     unique_items: [(UniqueItem, TilePos); UniqueItem::VARIANT_COUNT],
+    platforms: [Option<PlatformPosition>; MAX_PLATFORMS_PER_AREA * level::AREAS.len()],
+    enemies: [Option<EnemyPosition>; MAX_ENEMIES_PER_AREA * level::AREAS.len()],
+}
+
+#[derive(Clone, Copy)]
+pub struct PlacedArea {
+    area: Area,
+    /// Position on the area grid, i.e. in units of `AREA_SIZE`
+    offset: (u16, u16),
 }
 
 pub const MAX_UNIQUE_ITEM_POSITIONS : usize = 16;
 pub const MAX_UNIQUE_ITEMS_PER_ROOM: usize = 2;
+pub const MAX_PLATFORMS_PER_AREA: usize = 8;
+pub const MAX_ENEMIES_PER_AREA: usize = 16;
 pub type UniqueItemPositions = [Option<UniqueItemPosition>; MAX_UNIQUE_ITEM_POSITIONS];
 pub type UniqueItemsInThisRoom = [Option<UniqueItem>; MAX_UNIQUE_ITEMS_PER_ROOM];
+pub type PlatformsInThisArea = [Option<PlatformPosition>; MAX_PLATFORMS_PER_AREA];
+pub type EnemiesInThisArea = [Option<EnemyPosition>; MAX_ENEMIES_PER_AREA];
 
+#[derive(Clone, Copy)]
 pub struct Area {
     rooms: [RoomData; 32],
     meta: [RoomMetadata; 32],
     uniques: [UniqueItemsInThisRoom; 32],
+    music: Option<&'static Track>,
+    background: &'static [Layer],
+    platforms: PlatformsInThisArea,
+    enemies: EnemiesInThisArea,
 }
 
-pub struct AreaSource<const C: usize, const T:usize, const I:usize> {
+pub struct AreaSource<const C: usize, const T:usize, const I:usize, const P:usize, const E:usize> {
+    /// For error messages
+    pub name: &'static str,
     pub cells: &'static [u8],
     pub empty_tile_style: TileTypeEnum,
     pub solid_tile_style: TileTypeEnum,
+    /// Background music played while the player is in this area
+    pub music: Option<&'static Track>,
+    /// For each room, see `camera::camera_modes`
+    pub camera: [CameraMode; 32],
+    /// Parallax layers drawn behind the tiles, farthest first
+    pub background: &'static [Layer],
     char_lookup: [CharDescription; C],
     tile_lookup: [MappingBetweenCharAndTileType; T],
     item_lookup: [MappingBetweenCharAndItem; I],
+    platform_lookup: [MappingBetweenCharAndPlatform; P],
+    enemy_lookup: [MappingBetweenCharAndEnemy; E],
 }
 
 pub const LEVEL : Level = Level::new();
@@ -140,6 +199,18 @@ pub struct MappingBetweenCharAndItem {
     priority: bool,
 }
 
+#[derive(Clone, Copy)]
+pub struct MappingBetweenCharAndPlatform {
+    chr: u8,
+    kind: &'static PlatformKind,
+}
+
+#[derive(Clone, Copy)]
+pub struct MappingBetweenCharAndEnemy {
+    chr: u8,
+    kind: EnemyKind,
+}
+
 #[derive(Clone, Copy)]
 pub struct MappingBetweenCharAndTileType {
     chr: u8,
@@ -149,20 +220,28 @@ pub struct MappingBetweenCharAndTileType {
 
 pub struct Game {
     frame: u8,
+    /// Total number of frames played, persisted in the save file
+    frames_played: u32,
 
     pub camera: Camera,
     pub player: Player,
+    /// Gamepad state from before a freeze, so that presses made during it are not lost
+    held_gamepad: Option<u8>,
 
     pub world: World,
+    pub sounds: Sounds,
 }
 
 impl Game {
     pub const fn new() -> Game {
         let s = Game {
             frame: 0,
+            frames_played: 0,
             camera: Camera::new(),
             player: Player::new(),
+            held_gamepad: None,
             world: World::new(),
+            sounds: Sounds::new(),
         };
         s
     }
@@ -175,53 +254,72 @@ impl Game {
         let mut inhibit_drawing_player = false;
 
         if !self.player.pos.is_normal() {
-            self.player.pos = World::from_world_coords(LEVEL.unique_item_pos(UniqueItem::PlayerStart));
-            self.camera.pos = self.player.pos;
+            self.load_progress();
+            self.respawn();
         }
 
-        let newstate = self.player.control(prev_gamepad, gamepad);
-
-        #[allow(unused_variables)]
-        let mut iterations_counter = 0;
-        let mut remaining_movement_units = 10.0;
-        while remaining_movement_units > 0.0 {
-            self.player.grounded = false;
-            
-            //self.player.ground_force_direction += cf32::new(0.0, -0.02);
-            
-            let mut acceleration = cf32::new(0.0, 0.0);
-            self.player.handle_collisions(&mut acceleration);
-            self.player.movement(&mut acceleration);
-            
-            let vel_estimate1 = self.player.vel.norm();
-            let vel_estimate2 = (self.player.vel + acceleration*2.0).norm();
-            let vel_estimate = vel_estimate1.max(vel_estimate2  ) / 2000.0;
-
-            // aim to move do 5 iterations of collision calculations per pixel of movement
-            let mut epsilon = 0.2 / vel_estimate;
-
-            epsilon = epsilon.min(1.0/acceleration.norm());
-            epsilon = epsilon.max(0.1);
-            epsilon = epsilon.min(remaining_movement_units);
-
-            //crate::traceln!("  accel {} epsilon {}", (acceleration.norm() * 100.0) as i32, (epsilon * 100.0) as i32);
-
-            self.player.vel += epsilon * acceleration;
-            self.player.pos += epsilon * self.player.vel / 2000.0;
-
-            remaining_movement_units -= epsilon;
-            iterations_counter += 1;
+        let hold = self.camera.hold();
+        let newstate = if hold {
+            self.held_gamepad.get_or_insert(prev_gamepad);
+            MainState::Game
+        } else {
+            let prev_gamepad = self.held_gamepad.take().unwrap_or(prev_gamepad);
+            self.player.control(prev_gamepad, gamepad)
+        };
+
+        if self.player.take_respawn_request() {
+            self.respawn();
+        }
+
+        if !hold {
+            self.world.platforms.tick();
+            self.player.simulate_frame(&self.world.platforms);
+            self.world.animate(self.player.bounced.take());
+            self.world.enemies.tick(self.player.pos);
+            self.player.recover();
+            let radius = self.player.radius();
+            if let Some(source) = self.player.touching_hazard().or_else(|| self.world.enemies.touching(self.player.pos, radius)) {
+                let health = self.player.health;
+                let dead = self.player.hurt(source);
+                if self.player.health != health {
+                    self.camera.shake(3.0);
+                    self.camera.freeze(5);
+                }
+                if dead {
+                    self.respawn();
+                    self.player.revive();
+                }
+            }
+        }
+        if self.player.visited.visit(self.player.my_world_coords()) {
+            self.save_progress();
+        }
+        self.sounds.music.play(World::music_at(self.player.my_world_coords()));
+        self.sounds.requests.extend(self.player.sfx.take());
+        if matches!(newstate, MainState::Map) {
+            self.sounds.request(Sfx::MapOpen);
         }
-        //crate::traceln!("iters {}", iterations_counter);
 
         self.camera.update(&self.player, gamepad);
-        World::draw(self.frame, self.player.my_world_coords(), &self.camera);
+        self.world.draw(self.frames_played, self.player.my_world_coords(), &self.camera);
         
         let campos = World::to_world_coords(self.camera.pos);
         let playpos = World::to_world_coords(self.player.pos);
         for item in World::get_unique_items_around_tile(campos) {
             if let Some(item) = item {
-                unique_items::draw_unique(item, self.frame, playpos, &self.camera, &mut inhibit_drawing_player, &mut self.player.status);
+                let touch = unique_items::draw_unique(item, self.frame, playpos, &self.camera, &mut inhibit_drawing_player, &mut self.player.status);
+                let new_checkpoint = touch.now && item.is_checkpoint() && self.player.checkpoint != Some(item.get_pos());
+                if new_checkpoint {
+                    self.player.checkpoint = Some(item.get_pos());
+                }
+                // invisible items, like the player start, are touched without the player noticing
+                if touch.first_time && item.visible() {
+                    self.sounds.request(Sfx::ItemTouched);
+                    self.camera.punch(0.1);
+                }
+                if touch.first_time || new_checkpoint {
+                    self.save_progress();
+                }
             }
         }
 
@@ -229,9 +327,50 @@ impl Game {
             self.player.draw(self.frame, gamepad, &self.camera);
         }
 
+        self.draw_health();
+
         self.frame = self.frame.wrapping_add(1);
+        self.frames_played = self.frames_played.wrapping_add(1);
         newstate
     }
+
+    /// Put the player to the last checkpoint, or to the start if there is none
+    fn respawn(&mut self) {
+        let spawn = self.player.checkpoint.unwrap_or(LEVEL.unique_item_pos(UniqueItem::PlayerStart));
+        self.player.respawn(spawn);
+        self.world.enemies.reset();
+        self.camera.pos = self.player.pos;
+    }
+
+    fn draw_health(&self) {
+        for i in 0..player::MAX_HEALTH {
+            if i < self.player.health {
+                utils::draw_colours(3, 0, 0, 0);
+            } else {
+                utils::draw_colours(2, 0, 0, 0);
+            }
+            rect(2 + 6 * i as i32, 2, 4, 4);
+        }
+    }
+
+    /// Restore touched items, checkpoint and frame counter from disk, if there is a valid save.
+    fn load_progress(&mut self) {
+        if let Some(saved) = SaveData::load() {
+            self.player.status = saved.status;
+            self.player.visited = saved.visited;
+            self.player.checkpoint = saved.checkpoint;
+            self.frames_played = saved.frames;
+        }
+    }
+
+    fn save_progress(&self) {
+        SaveData {
+            status: self.player.status,
+            visited: self.player.visited,
+            checkpoint: self.player.checkpoint,
+            frames: self.frames_played,
+        }.store();
+    }
 }
 
 
@@ -263,6 +402,7 @@ impl GlobalState {
             MainState::Game => self.game.tick(gamepad_state, self.previous_gamepad),
             MainState::Map => self.map_viewer.tick(gamepad_state, self.previous_gamepad, &self.game),
         };
+        self.game.sounds.update();
         self.previous_gamepad = gamepad_state;
     }
 }
@@ -276,3 +416,178 @@ fn update() {
         GLOBAL_STATE.tick(*GAMEPAD1);
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_runs_headlessly() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        for _ in 0..120 {
+            game.tick(0, 0);
+        }
+        assert!(game.player.pos.is_normal());
+        assert!(game.player.grounded);
+        let (x, y) = game.player.my_world_coords();
+        let (sx, sy) = UniqueItem::PlayerStart.get_pos();
+        assert!((x as i32 - sx as i32).abs() <= 1 && (y as i32 - sy as i32).abs() <= 1);
+
+        let drawn = (0..SCREEN_SIZE).flat_map(|y| (0..SCREEN_SIZE).map(move |x| (x, y)))
+            .filter(|&(x, y)| wasm4::get_pixel(x, y) != 0)
+            .count();
+        assert!(drawn > 0);
+    }
+
+    #[test]
+    fn spawning_makes_no_item_sound() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        game.tick(0, 0);
+        assert!(game.player.status.is_touched(UniqueItem::PlayerStart));
+        assert!(!game.sounds.requests.contains(Sfx::ItemTouched));
+    }
+
+    #[test]
+    fn progress_survives_restart() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        for _ in 0..10 {
+            game.tick(0, 0);
+        }
+        assert!(game.player.status.is_touched(UniqueItem::PlayerStart));
+        // entering a room for the first time saves it as visited, away from the items in it
+        let checkpoint = UniqueItem::CheckpointCave.get_pos();
+        assert!(!game.player.visited.is_visited(checkpoint));
+        let x = (checkpoint.0 & !15..checkpoint.0 | 15)
+            .find(|&x| x.abs_diff(checkpoint.0) > 4 && tiles::TileType::collision_configuration(World::get_tile((x, checkpoint.1))).is_empty())
+            .unwrap();
+        game.player.pos = World::from_world_coords((x, checkpoint.1));
+        game.tick(0, 0);
+        assert!(!game.player.status.is_touched(UniqueItem::CheckpointCave));
+
+        let mut game = Game::new();
+        game.tick(0, 0);
+        assert!(game.player.status.is_touched(UniqueItem::PlayerStart));
+        assert_eq!(game.player.checkpoint, Some(UniqueItem::PlayerStart.get_pos()));
+        assert!(game.player.visited.is_visited(checkpoint));
+    }
+
+    #[test]
+    fn garbage_on_disk_means_fresh_game() {
+        let _guard = wasm4::exclusive();
+        let garbage = [0x5Au8; save::SAVE_SIZE];
+        unsafe {
+            diskw(garbage.as_ptr(), garbage.len() as u32);
+        }
+        let mut game = Game::new();
+        game.tick(0, 0);
+        assert_eq!(game.frames_played, 1);
+        assert!(!game.player.status.is_touched(UniqueItem::InfoWelcome));
+    }
+
+    #[test]
+    fn hazards_hurt_until_respawn() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        for _ in 0..10 {
+            game.tick(0, 0);
+        }
+        let checkpoint = game.player.checkpoint.unwrap();
+        let spikes = (0..World::BOTTOM_RIGHT_TILE.1).flat_map(|y| (0..World::BOTTOM_RIGHT_TILE.0).map(move |x| (x, y)))
+            .find(|&t| tiles::TileType::hurts(World::get_tile(t)))
+            .unwrap();
+
+        for hit in 1..=player::MAX_HEALTH {
+            game.player.pos = World::from_world_coords(spikes) + cf32::new(0.0, -6.0);
+            game.player.vel = cf32::new(0.0, 0.0);
+            let health = game.player.health;
+            for _ in 0..200 {
+                game.tick(0, 0);
+                if game.player.health != health {
+                    break;
+                }
+            }
+            if hit < player::MAX_HEALTH {
+                assert_eq!(game.player.health, player::MAX_HEALTH - hit);
+            }
+        }
+        assert_eq!(game.player.health, player::MAX_HEALTH);
+        let (x, y) = game.player.my_world_coords();
+        assert!((x as i32 - checkpoint.0 as i32).abs() <= 1 && (y as i32 - checkpoint.1 as i32).abs() <= 1);
+    }
+
+    #[test]
+    fn long_press_respawns_at_last_checkpoint() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        game.tick(0, 0);
+        let checkpoint = UniqueItem::CheckpointCave.get_pos();
+        game.player.pos = World::from_world_coords(checkpoint);
+        game.camera.pos = game.player.pos;
+        game.tick(0, 0);
+        assert_eq!(game.player.checkpoint, Some(checkpoint));
+
+        // a short press opens the map
+        game.tick(BUTTON_1, 0);
+        assert!(matches!(game.tick(0, BUTTON_1), MainState::Map));
+
+        // next to the start, which is a checkpoint too
+        let start = UniqueItem::PlayerStart.get_pos();
+        game.player.pos = World::from_world_coords((start.0 + 1, start.1));
+        game.player.vel = cf32::new(300.0, -200.0);
+        let mut prev = 0;
+        for _ in 0..60 {
+            assert!(matches!(game.tick(BUTTON_1, prev), MainState::Game));
+            prev = BUTTON_1;
+        }
+        assert!(matches!(game.tick(0, BUTTON_1), MainState::Game));
+        assert_eq!(game.player.my_world_coords(), checkpoint);
+        assert!(game.player.vel.re.abs() < 1.0);
+        assert!(game.player.jump_dir.is_none());
+    }
+
+    #[test]
+    fn freeze_keeps_player_still_but_remembers_presses() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        for _ in 0..60 {
+            game.tick(0, 0);
+        }
+        let (pos, vel) = (game.player.pos, game.player.vel);
+        game.camera.freeze(3);
+        let held = BUTTON_1 | BUTTON_RIGHT;
+        let mut prev = 0;
+        for _ in 0..3 {
+            assert!(matches!(game.tick(held, prev), MainState::Game));
+            prev = held;
+        }
+        assert_eq!((game.player.pos, game.player.vel), (pos, vel));
+        // the press made during the freeze still counts as a short one
+        game.tick(held, held);
+        assert!(matches!(game.tick(0, held), MainState::Map));
+    }
+
+    #[test]
+    fn jumping_and_landing_make_sounds() {
+        let _guard = wasm4::exclusive();
+        let mut state = GlobalState::new();
+        let mut tones = vec![];
+        for frame in 0..200 {
+            let gamepad = match frame {
+                60 => BUTTON_2,
+                61 => BUTTON_UP,
+                _ => 0,
+            };
+            state.tick(gamepad);
+            let played = wasm4::take_tones();
+            if frame >= 60 {
+                tones.extend(played);
+            }
+        }
+        let channels: Vec<u32> = tones.iter().map(|t| t[3] & 0b11).collect();
+        assert_eq!(channels.first(), Some(&TONE_PULSE1));
+        assert!(channels.contains(&TONE_NOISE));
+    }
+}
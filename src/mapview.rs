@@ -1,30 +1,45 @@
-use crate::{Game, MainState, TilePos, UniqueItem, wasm4::{BUTTON_1, FRAMEBUFFER, PALETTE, SCREEN_SIZE}, world::World};
+use crate::{Game, MainState, TilePos, UniqueItem, VisitedRooms, wasm4::{BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, FRAMEBUFFER, PALETTE, SCREEN_SIZE}, world::World};
 use crate::tiles::TileType;
 use enum_iterator::IntoEnumIterator;
 
+/// Panning speed, in screen pixels per frame
+const PAN_SPEED: i32 = 4;
+
 pub struct MapViewer {
-    upper_left_tile: TilePos,
+    /// Tile shown in the middle of the screen. `None` means recentering on the player when the map opens.
+    center_tile: Option<(i32, i32)>,
+    /// Screen pixels per tile: 1, 2 or 4
+    zoom: u8,
     blinker : u8,
 }
 
 impl MapViewer {
     pub const fn new() -> MapViewer {
         MapViewer {
-            upper_left_tile: (u16::MAX, u16::MAX),
+            center_tile: None,
+            zoom: 1,
             blinker: 0,
         }
     }
 
-    pub fn clamp_tile(mut pos: (i32, i32)) -> TilePos {
-        if pos.0 >= World::BOTTOM_RIGHT_TILE.0 as i32 - SCREEN_SIZE as i32 {
-            pos.0 =  World::BOTTOM_RIGHT_TILE.0 as i32 - SCREEN_SIZE as i32 - 1;
-        }
-        if pos.1 >= World::BOTTOM_RIGHT_TILE.1 as i32 - SCREEN_SIZE as i32{
-            pos.1 =  World::BOTTOM_RIGHT_TILE.1 as i32 - SCREEN_SIZE as i32 - 1;
-        }
-        if pos.0 < 0 { pos.0 = 0; }
-        if pos.1 < 0 { pos.1 = 0; }
-        (pos.0 as u16 & !0b11, pos.1 as u16 & !0b11)
+    /// Keep the view within the world, or center the world if it is smaller than the view
+    pub fn clamp_center(pos: (i32, i32), zoom: u8) -> (i32, i32) {
+        let view = SCREEN_SIZE as i32 / zoom as i32;
+        let clamp = |x: i32, world: u16| {
+            let world = world as i32;
+            if world <= view {
+                world / 2
+            } else {
+                x.clamp(view / 2, world - view / 2)
+            }
+        };
+        (clamp(pos.0, World::BOTTOM_RIGHT_TILE.0), clamp(pos.1, World::BOTTOM_RIGHT_TILE.1))
+    }
+
+    fn upper_left_tile(&self) -> (i32, i32) {
+        let view = SCREEN_SIZE as i32 / self.zoom as i32;
+        let (x, y) = self.center_tile.unwrap_or_default();
+        (x - view / 2, y - view / 2)
     }
 
     pub fn tick(&mut self, cur: u8, prev: u8, game: &Game) -> MainState {
@@ -33,31 +48,45 @@ impl MapViewer {
         }
 
         if (cur & !prev) & BUTTON_1 != 0{
-            self.upper_left_tile = (u16::MAX, u16::MAX);
+            self.center_tile = None;
             return MainState::Game;
         }
 
-        if self.upper_left_tile == (u16::MAX, u16::MAX) {
-            let playerpos = World::to_world_coords(game.player.pos);
-            let (x,y) = (playerpos.0 as i32, playerpos.1 as i32);
-            self.upper_left_tile = MapViewer::clamp_tile((x - (SCREEN_SIZE as i32)/2, y - (SCREEN_SIZE as i32)/2));
+        if (cur & !prev) & BUTTON_2 != 0 {
+            self.zoom = match self.zoom {
+                1 => 2,
+                2 => 4,
+                _ => 1,
+            };
         }
 
+        let (mut x, mut y) = match self.center_tile {
+            Some(x) => x,
+            None => {
+                let playerpos = World::to_world_coords(game.player.pos);
+                (playerpos.0 as i32, playerpos.1 as i32)
+            }
+        };
+        let step = PAN_SPEED / self.zoom as i32;
+        if cur & BUTTON_LEFT != 0 { x -= step; }
+        if cur & BUTTON_RIGHT != 0 { x += step; }
+        if cur & BUTTON_UP != 0 { y -= step; }
+        if cur & BUTTON_DOWN != 0 { y += step; }
+        self.center_tile = Some(MapViewer::clamp_center((x, y), self.zoom));
+
+        let visited = &game.player.visited;
+        let (left, top) = self.upper_left_tile();
+        let zoom = self.zoom as i32;
         for y in 0..SCREEN_SIZE {
             for x in (0..SCREEN_SIZE).step_by(4) {
-                let tt1 = World::get_tile((self.upper_left_tile.0 + x as u16 + 0, self.upper_left_tile.1 + y as u16)).map_viewer_colour();
-                let tt2 = World::get_tile((self.upper_left_tile.0 + x as u16 + 1, self.upper_left_tile.1 + y as u16)).map_viewer_colour();
-                let tt3 = World::get_tile((self.upper_left_tile.0 + x as u16 + 2, self.upper_left_tile.1 + y as u16)).map_viewer_colour();
-                let tt4 = World::get_tile((self.upper_left_tile.0 + x as u16 + 3, self.upper_left_tile.1 + y as u16)).map_viewer_colour();
-                let byte = 
-                    (((tt1) & 0b11) << 0) | 
-                    (((tt2) & 0b11) << 2) | 
-                    (((tt3) & 0b11) << 4) | 
-                    (((tt4) & 0b11) << 6) | 
-                    0;
-                let offset = ((SCREEN_SIZE * y >> 2) + (x>>2)) as isize;
+                let mut byte = 0;
+                for i in 0..4 {
+                    let tile = (left + (x + i) as i32 / zoom, top + y as i32 / zoom);
+                    byte |= (MapViewer::colour_at(visited, tile, (x + i, y)) & 0b11) << (2 * i);
+                }
+                let offset = (((SCREEN_SIZE * y) >> 2) + (x>>2)) as isize;
                 unsafe {
-                    let ptr =(FRAMEBUFFER as *mut u8).offset(offset); 
+                    let ptr =(FRAMEBUFFER as *mut u8).offset(offset);
                     ptr.write(byte);
                 }
             }
@@ -66,15 +95,15 @@ impl MapViewer {
         for item in UniqueItem::into_enum_iter() {
             if item.visible() {
                 let itempos = item.get_pos();
-                if game.player.status.is_touched(item) || self.blinker < 30 {
-                    self.set_pixel(itempos, 0b11);
+                if visited.is_visited(itempos) && (game.player.status.is_touched(item) || self.blinker < 30) {
+                    self.mark_tile(itempos, 0b11);
                 }
             }
         }
 
         if self.blinker < 30 {
             let playerpos = World::to_world_coords(game.player.pos);
-            self.set_pixel(playerpos, 0b10);
+            self.mark_tile(playerpos, 0b10);
         }
 
 
@@ -85,18 +114,98 @@ impl MapViewer {
         MainState::Map
     }
 
-    fn set_pixel(&self, pos: TilePos, colour: u8) {
-        match (pos.0.checked_sub(self.upper_left_tile.0), pos.1.checked_sub(self.upper_left_tile.1)) {
-            (Some(x), Some(y)) if x < SCREEN_SIZE as u16 && y < SCREEN_SIZE as u16 => {
-                let offset = ((SCREEN_SIZE * y as u32 >> 2) + (x as u32>>2)) as isize;
-                let shift = (x & 0b11) << 1;
-                let bitmask = 0b11 << shift;
-                unsafe {
-                    let ptr = (FRAMEBUFFER as *mut u8).offset(offset);
-                    ptr.write((ptr.read() & !bitmask) | (colour << shift) );
+    /// Colour of a map pixel. Rooms not visited yet are shown as sparse dots.
+    fn colour_at(visited: &VisitedRooms, (x, y): (i32, i32), (screen_x, screen_y): (u32, u32)) -> u8 {
+        if x < 0 || y < 0 {
+            return 0;
+        }
+        let tile = (x as u16, y as u16);
+        if !visited.is_visited(tile) {
+            return if screen_x % 4 == 0 && screen_y % 4 == 0 { 1 } else { 0 };
+        }
+        World::get_tile(tile).map_viewer_colour()
+    }
+
+    /// Fill the square of screen pixels showing the tile
+    fn mark_tile(&self, pos: TilePos, colour: u8) {
+        let (left, top) = self.upper_left_tile();
+        let zoom = self.zoom as i32;
+        let (sx, sy) = ((pos.0 as i32 - left) * zoom, (pos.1 as i32 - top) * zoom);
+        for y in sy..sy+zoom {
+            for x in sx..sx+zoom {
+                if x >= 0 && y >= 0 && x < SCREEN_SIZE as i32 && y < SCREEN_SIZE as i32 {
+                    MapViewer::set_pixel(x as u32, y as u32, colour);
                 }
             }
-            _ => (),
         }
     }
+
+    fn set_pixel(x: u32, y: u32, colour: u8) {
+        let offset = (((SCREEN_SIZE * y) >> 2) + (x>>2)) as isize;
+        let shift = (x & 0b11) << 1;
+        let bitmask = 0b11 << shift;
+        unsafe {
+            let ptr = (FRAMEBUFFER as *mut u8).offset(offset);
+            ptr.write((ptr.read() & !bitmask) | (colour << shift) );
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::wasm4::{self, get_pixel};
+
+    fn opened_map(game: &Game) -> MapViewer {
+        let mut map = MapViewer::new();
+        map.tick(0, 0, game);
+        map
+    }
+
+    #[test]
+    fn unvisited_rooms_are_hidden() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        game.tick(0, 0);
+        let map = opened_map(&game);
+        let (left, top) = map.upper_left_tile();
+
+        // A solid tile in a room the player has not been to, away from the fog dots
+        let (x, y) = (0..SCREEN_SIZE).flat_map(|y| (1..SCREEN_SIZE).step_by(4).map(move |x| (x, y)))
+            .find(|&(x, y)| {
+                let tile = ((left + x as i32) as u16, (top + y as i32) as u16);
+                top + y as i32 >= 0 && World::get_tile(tile).map_viewer_colour() != 0 && !game.player.visited.is_visited(tile)
+            })
+            .unwrap();
+        assert_eq!(get_pixel(x, y), 0);
+
+        game.player.visited.visit(((left + x as i32) as u16, (top + y as i32) as u16));
+        opened_map(&game);
+        assert_eq!(get_pixel(x, y), 1);
+    }
+
+    #[test]
+    fn panning_and_zoom() {
+        let _guard = wasm4::exclusive();
+        let mut game = Game::new();
+        game.tick(0, 0);
+        let mut map = opened_map(&game);
+        let start = map.center_tile.unwrap();
+
+        map.tick(BUTTON_2, 0, &game);
+        assert_eq!(map.zoom, 2);
+        for _ in 0..5 {
+            map.tick(BUTTON_RIGHT, BUTTON_RIGHT, &game);
+        }
+        assert_eq!(map.center_tile.unwrap(), (start.0 + 10, start.1));
+
+        // panning stops at the world border
+        for _ in 0..1000 {
+            map.tick(BUTTON_LEFT | BUTTON_UP, BUTTON_LEFT | BUTTON_UP, &game);
+        }
+        assert_eq!(map.upper_left_tile().0, 0);
+
+        map.tick(BUTTON_1, 0, &game);
+        assert_eq!(map.center_tile, None);
+    }
 }
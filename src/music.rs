@@ -0,0 +1,294 @@
+//! Tracker-style background music.
+//!
+//! A `Track` has one pattern per `tone` channel. Patterns are sequences of steps written as text (see `pattern`);
+//! `MusicPlayer` advances one step each `Track::speed` frames and starts a tone for each note.
+
+use crate::utils::text_error;
+use crate::wasm4::{tone, TONE_MODE1, TONE_MODE2};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Step {
+    /// Keep playing the previous note
+    Hold,
+    Rest,
+    /// Semitones above C-0
+    Note(u8),
+}
+
+/// Frequencies of the 4th octave, starting from C
+const OCTAVE4: [u16; 12] = [262, 277, 294, 311, 330, 349, 370, 392, 415, 440, 466, 494];
+
+pub const fn note_frequency(note: u8) -> u16 {
+    let octave = note / 12;
+    let f = OCTAVE4[(note % 12) as usize];
+    if octave >= 4 { f << (octave - 4) } else { f >> (4 - octave) }
+}
+
+/// Parse a pattern of exactly `N` steps. Steps are written between `|` markers, separated by single spaces:
+/// `C-4` or `C#4` is a note (octaves 1 to 8), `...` holds the previous note and `---` is silence.
+/// There can be multiple lines of steps.
+pub const fn pattern<const N: usize>(s: &'static [u8]) -> [Step; N] {
+    let mut buf = [Step::Rest; N];
+    let mut stepidx = 0;
+    let mut lineidx = 0;
+    let mut column = 0;
+    let mut within_pattern_area = false;
+
+    let mut i = 0;
+    while i < s.len() {
+        let chr = s[i];
+        if chr == b'|' {
+            if within_pattern_area && column % 4 != 3 {
+                text_error("Music pattern", lineidx, column, chr, "each step must be exactly 3 characters long");
+            }
+            within_pattern_area = !within_pattern_area;
+            if !within_pattern_area {
+                lineidx += 1;
+            }
+            column = 0;
+            i += 1;
+            continue;
+        }
+        if !within_pattern_area {
+            i += 1;
+            continue;
+        }
+
+        if column % 4 == 3 {
+            if chr != b' ' {
+                text_error("Music pattern", lineidx, column, chr, "steps must be separated by single spaces");
+            }
+            column += 1;
+            i += 1;
+            continue;
+        }
+
+        if column % 4 == 0 {
+            if i + 2 >= s.len() {
+                text_error("Music pattern", lineidx, column, chr, "unterminated line");
+            }
+            if stepidx >= N {
+                text_error("Music pattern", lineidx, column, chr, "too many steps");
+            }
+            let (c1, c2) = (s[i+1], s[i+2]);
+            buf[stepidx] = match (chr, c1, c2) {
+                (b'.', b'.', b'.') => Step::Hold,
+                (b'-', b'-', b'-') => Step::Rest,
+                _ => {
+                    let semitone: u8 = match chr {
+                        b'C' => 0,
+                        b'D' => 2,
+                        b'E' => 4,
+                        b'F' => 5,
+                        b'G' => 7,
+                        b'A' => 9,
+                        b'B' => 11,
+                        _ => text_error("Music pattern", lineidx, column, chr, "expected note name A-G, `...` or `---`"),
+                    };
+                    let sharp = match c1 {
+                        b'-' => 0,
+                        b'#' if semitone != 4 && semitone != 11 => 1,
+                        _ => text_error("Music pattern", lineidx, column+1, c1, "expected `-` or `#` after a note name"),
+                    };
+                    let octave = match c2 {
+                        b'1'..=b'8' => c2 - b'0',
+                        _ => text_error("Music pattern", lineidx, column+2, c2, "expected octave 1-8"),
+                    };
+                    Step::Note(octave * 12 + semitone + sharp)
+                }
+            };
+            stepidx += 1;
+        }
+        column += 1;
+        i += 1;
+    }
+    if within_pattern_area {
+        text_error("Music pattern", lineidx, column, b'|', "missing closing | marker");
+    }
+    if stepidx != N {
+        text_error("Music pattern", lineidx, 0, b'|', "number of steps does not match pattern length");
+    }
+    buf
+}
+
+#[derive(Clone, Copy)]
+pub struct Channel {
+    /// Empty pattern means the channel is not used. Patterns loop independently of each other.
+    pub pattern: &'static [Step],
+    pub volume: u8,
+    /// Mode (duty cycle) bits of `tone` flags
+    pub mode: u32,
+}
+
+impl Channel {
+    pub const SILENT: Channel = Channel { pattern: &[], volume: 0, mode: TONE_MODE1 };
+}
+
+pub struct Track {
+    pub name: &'static str,
+    /// Frames per step
+    pub speed: u8,
+    /// In `TONE_*` channel order: pulse 1, pulse 2, triangle, noise
+    pub channels: [Channel; 4],
+}
+
+impl Track {
+    pub const fn new(name: &'static str, speed: u8, channels: [Channel; 4]) -> Track {
+        if speed == 0 {
+            crate::utils::ConstMsg::new("Track ").s(name).s(" must have non-zero speed").panic();
+        }
+        let mut i = 0;
+        while i < 4 {
+            if !channels[i].pattern.is_empty() && matches!(channels[i].pattern[0], Step::Hold) {
+                crate::utils::ConstMsg::new("Track ").s(name).s(": pattern must not begin with `...`").panic();
+            }
+            i += 1;
+        }
+        Track { name, speed, channels }
+    }
+}
+
+pub struct MusicPlayer {
+    track: Option<&'static Track>,
+    frame: u32,
+}
+
+impl MusicPlayer {
+    pub const fn new() -> MusicPlayer {
+        MusicPlayer {
+            track: None,
+            frame: 0,
+        }
+    }
+
+    /// Switch to a track, restarting from the beginning if it differs from the current one
+    pub fn play(&mut self, track: Option<&'static Track>) {
+        let same = match (self.track, track) {
+            (Some(a), Some(b)) => core::ptr::eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same {
+            self.track = track;
+            self.frame = 0;
+        }
+    }
+
+    /// Advance by one frame. Notes are not started on `busy` channels (e.g. playing sound effects).
+    pub fn update(&mut self, busy: [bool; 4]) {
+        let track = match self.track {
+            Some(x) => x,
+            None => return,
+        };
+        let speed = track.speed as u32;
+        if self.frame.is_multiple_of(speed) {
+            let stepidx = (self.frame / speed) as usize;
+            for (n, ch) in track.channels.iter().enumerate() {
+                if ch.pattern.is_empty() || busy[n] {
+                    continue;
+                }
+                let len = ch.pattern.len();
+                if let Step::Note(note) = ch.pattern[stepidx % len] {
+                    let mut steps = 1;
+                    while steps < len && ch.pattern[(stepidx + steps) % len] == Step::Hold {
+                        steps += 1;
+                    }
+                    let duration = steps as u32 * speed - 1;
+                    tone(note_frequency(note) as u32, ch.volume as u32, duration, n as u32 | ch.mode);
+                }
+            }
+        }
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
+
+static CAVE_LEAD: [Step; 16] = pattern(b"
+    |A-4 ... C-5 ... E-5 ... ... --- D-5 ... C-5 ... B-4 ... ... ---|
+");
+static CAVE_BASS: [Step; 8] = pattern(b"
+    |A-2 ... ... ... F-2 ... G-2 ...|
+");
+static CAVE_DRUMS: [Step; 4] = pattern(b"
+    |C-6 --- C-8 ---|
+");
+
+pub static CAVE: Track = Track::new("cave", 12, [
+    Channel { pattern: &CAVE_LEAD, volume: 12, mode: TONE_MODE2 },
+    Channel::SILENT,
+    Channel { pattern: &CAVE_BASS, volume: 20, mode: TONE_MODE1 },
+    Channel { pattern: &CAVE_DRUMS, volume: 6, mode: TONE_MODE1 },
+]);
+
+static CORRIDOR_LEAD: [Step; 16] = pattern(b"
+    |E-5 --- E-5 G-5 ... --- E-5 ---|
+    |D-5 ... C-5 ... D-5 --- --- ---|
+");
+static CORRIDOR_BASS: [Step; 8] = pattern(b"
+    |C-3 ... G-2 ... A-2 ... G-2 ...|
+");
+
+pub static CORRIDOR: Track = Track::new("corridor", 10, [
+    Channel { pattern: &CORRIDOR_LEAD, volume: 12, mode: TONE_MODE2 },
+    Channel::SILENT,
+    Channel { pattern: &CORRIDOR_BASS, volume: 20, mode: TONE_MODE1 },
+    Channel::SILENT,
+]);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::wasm4::{self, take_tones};
+
+    #[test]
+    fn notation() {
+        const P: [Step; 6] = pattern(b"
+            |C-4 C#4 ...|
+            |--- A-4 B-1|
+        ");
+        assert_eq!(P, [Step::Note(48), Step::Note(49), Step::Hold, Step::Rest, Step::Note(57), Step::Note(23)]);
+        assert_eq!(note_frequency(57), 440);
+        assert_eq!(note_frequency(69), 880);
+        assert_eq!(note_frequency(45), 220);
+    }
+
+    #[test]
+    #[should_panic(expected = "Music pattern line 1, column 6, character '#': expected `-` or `#` after a note name")]
+    fn no_e_sharp() {
+        pattern::<2>(b"|C-4 E#4|");
+    }
+
+    #[test]
+    #[should_panic(expected = "number of steps does not match pattern length")]
+    fn wrong_length() {
+        pattern::<3>(b"|C-4 E-4|");
+    }
+
+    #[test]
+    fn player_steps_through_patterns() {
+        let _guard = wasm4::exclusive();
+        let mut player = MusicPlayer::new();
+        player.play(Some(&CAVE));
+        player.update([false; 4]);
+        let tones = take_tones();
+        assert_eq!(tones.len(), 3);
+        // A-4 held for two steps
+        assert_eq!(tones[0], [440, 12, 2 * 12 - 1, TONE_MODE2]);
+
+        // second step only holds notes or rests
+        for _ in 1..2 * CAVE.speed {
+            player.update([false; 4]);
+        }
+        assert!(take_tones().is_empty());
+
+        // C-5 is not played as the lead channel is busy
+        player.update([true, false, false, false]);
+        assert_eq!(take_tones().iter().map(|t| t[0]).collect::<Vec<_>>(), [4192]);
+
+        player.play(Some(&CAVE));
+        player.update([false; 4]);
+        assert!(take_tones().is_empty());
+        player.play(Some(&CORRIDOR));
+        player.update([false; 4]);
+        assert_eq!(take_tones().len(), 2);
+    }
+}
@@ -0,0 +1,189 @@
+//! Moving platforms: entities with their own collision segments, placed from the level text like unique items.
+//!
+//! Positions depend only on the frame counter, so platforms need no per-entity state and stay reproducible in replays.
+
+use crate::{Scalar, TilePos, camera::Camera, cf32, sprites, tiles::CollisionSegment, utils::draw_colours, wasm4::{SCREEN_SIZE, blit}, world::World};
+
+#[derive(PartialEq, Debug)]
+pub struct PlatformKind {
+    /// Width in tiles
+    pub width: u8,
+    /// Waypoints relative to the placement position, in tiles.
+    /// The platform moves through them and then back to where it was placed, in a loop.
+    pub path: &'static [(i8, i8)],
+    /// Frames to move by one tile
+    pub frames_per_tile: u16,
+}
+
+impl PlatformKind {
+    /// Distance between consecutive waypoints, in tiles
+    const fn leg_length(from: (i8, i8), to: (i8, i8)) -> u32 {
+        let dx = (to.0 as i32 - from.0 as i32).unsigned_abs();
+        let dy = (to.1 as i32 - from.1 as i32).unsigned_abs();
+        if dx > dy { dx } else { dy }
+    }
+
+    const fn waypoint(&self, i: usize) -> (i8, i8) {
+        if i == 0 || i > self.path.len() { (0, 0) } else { self.path[i - 1] }
+    }
+
+    /// Frames needed to go around the whole path
+    pub const fn period(&self) -> u32 {
+        let mut total = 0;
+        let mut i = 0;
+        while i <= self.path.len() {
+            total += PlatformKind::leg_length(self.waypoint(i), self.waypoint(i + 1));
+            i += 1;
+        }
+        total * self.frames_per_tile as u32
+    }
+
+    /// Offset from the placement position at the given frame, in pixels
+    pub fn offset(&self, frame: u32) -> cf32 {
+        let period = self.period();
+        if period == 0 {
+            return cf32::new(0.0, 0.0);
+        }
+        let mut t = frame % period;
+        let mut i = 0;
+        loop {
+            let (from, to) = (self.waypoint(i), self.waypoint(i + 1));
+            let leg = PlatformKind::leg_length(from, to) * self.frames_per_tile as u32;
+            if t < leg {
+                let k = t as f32 / leg as f32;
+                let from = cf32::new(from.0 as f32, from.1 as f32);
+                let to = cf32::new(to.0 as f32, to.1 as f32);
+                return 8.0 * (from + (to - from) * k);
+            }
+            t -= leg;
+            i += 1;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct PlatformPosition {
+    pub kind: &'static PlatformKind,
+    /// Leftmost tile of the platform at frame 0
+    pub pos: TilePos,
+}
+
+/// Each tile of a platform collides like a solid block
+static PLATFORM_TILE: [CollisionSegment; 4] = [
+    CollisionSegment{rp1: cf32::new(-3.0,  -3.0), rp2: cf32::new(3.0,  -3.0), rad:1.0, el: 0.01},
+    CollisionSegment{rp1: cf32::new( 3.0,  -3.0), rp2: cf32::new(3.0,   3.0), rad:1.0, el: 0.01},
+    CollisionSegment{rp1: cf32::new( 3.0,   3.0), rp2: cf32::new(-3.0,  3.0), rad:1.0, el: 0.01},
+    CollisionSegment{rp1: cf32::new(-3.0,   3.0), rp2: cf32::new(-3.0, -3.0), rad:1.0, el: 0.01},
+];
+
+pub const LIFT: PlatformKind = PlatformKind {
+    width: 3,
+    path: &[(0, -6)],
+    frames_per_tile: 12,
+};
+
+#[derive(Clone)]
+pub struct Platforms {
+    frame: u32,
+}
+
+impl Platforms {
+    pub const fn new() -> Platforms {
+        Platforms {
+            frame: 0,
+        }
+    }
+
+    /// Advance platforms by one frame
+    pub fn tick(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    /// Center of the leftmost tile of a platform, in pixels
+    pub fn position(&self, platform: &PlatformPosition) -> cf32 {
+        World::from_world_coords(platform.pos) + platform.kind.offset(self.frame)
+    }
+
+    /// How much the platform has moved during the last frame
+    pub fn displacement(&self, platform: &PlatformPosition) -> cf32 {
+        let kind = platform.kind;
+        kind.offset(self.frame) - kind.offset(self.frame.wrapping_sub(1))
+    }
+
+    /// Call `f` with index (in `World::platforms`), center and collision geometry of each platform tile
+    /// within `distance` pixels of `pos`
+    pub fn tiles_near(&self, pos: cf32, distance: f32, mut f: impl FnMut(usize, cf32, &'static [CollisionSegment])) {
+        for (i, platform) in World::platforms().iter().enumerate() {
+            let platform = match platform {
+                Some(x) => x,
+                None => continue,
+            };
+            let leftmost = self.position(platform);
+            for t in 0..platform.kind.width {
+                let center = leftmost + cf32::new(8.0 * t as f32, 0.0);
+                if (center - pos).norm_sqr() < distance * distance {
+                    f(i, center, &PLATFORM_TILE);
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, cam: &Camera) {
+        draw_colours(2, 0, 0, 0);
+        for platform in World::platforms().iter().flatten() {
+            let leftmost = self.position(platform);
+            for t in 0..platform.kind.width {
+                let center = leftmost + cf32::new(8.0 * t as f32, 0.0);
+                let upperleft = cam.to_screen(center) - cf32::new(4.0, 4.0);
+                if upperleft.re < -8.0 || upperleft.im < -8.0 || upperleft.re > SCREEN_SIZE as f32 || upperleft.im > SCREEN_SIZE as f32 {
+                    continue;
+                }
+                blit(&sprites::PLATFORM, upperleft.re.to_f32() as i32, upperleft.im.to_f32() as i32, 8, 8, 0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+
+    #[test]
+    fn path_loops() {
+        static SQUARE: PlatformKind = PlatformKind { width: 1, path: &[(2, 0), (2, -2), (0, -2)], frames_per_tile: 4 };
+        assert_eq!(SQUARE.period(), 32);
+        assert_eq!(SQUARE.offset(0), cf32::new(0.0, 0.0));
+        assert_eq!(SQUARE.offset(4), cf32::new(8.0, 0.0));
+        assert_eq!(SQUARE.offset(12), cf32::new(16.0, -8.0));
+        assert_eq!(SQUARE.offset(28), cf32::new(0.0, -8.0));
+        assert_eq!(SQUARE.offset(32), SQUARE.offset(0));
+    }
+
+    #[test]
+    fn player_is_carried() {
+        let (i, lift) = World::platforms().iter().enumerate()
+            .find_map(|(i, p)| p.filter(|p| *p.kind == LIFT).map(|p| (i, p)))
+            .unwrap();
+        let mut platforms = Platforms::new();
+        let mut player = Player::new();
+        player.pos = platforms.position(&lift) + cf32::new(8.0, -8.0);
+        let frame = |player: &mut Player, platforms: &mut Platforms| {
+            platforms.tick();
+            player.control(0, 0);
+            player.simulate_frame(platforms);
+        };
+        for _ in 0..20 {
+            frame(&mut player, &mut platforms);
+        }
+        assert_eq!(player.riding, Some(i));
+
+        let relative = player.pos - platforms.position(&lift);
+        let height = platforms.position(&lift).im;
+        for _ in 0..30 {
+            frame(&mut player, &mut platforms);
+        }
+        assert!(platforms.position(&lift).im < height - 16.0);
+        assert!((player.pos - platforms.position(&lift) - relative).norm() < 1.0);
+    }
+}
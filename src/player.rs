@@ -1,41 +1,109 @@
 
-use crate::tiles::{CollisionSegment, TileType};
-use crate::{Camera, MainState, TilePos, cf32};
-use crate::wasm4::{BLIT_1BPP, BLIT_FLIP_X, BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, SCREEN_SIZE, blit, line};
+use crate::tiles::{CollisionSegment, Physics, TileType};
+use crate::{Camera, MainState, Real, Scalar, TilePos, cf32, real};
+use crate::wasm4::{BLIT_1BPP, BLIT_FLIP_X, BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, blit, line};
 use crate::World;
 use crate::utils::draw_colours;
 use crate::sprites;
+use crate::sound::{Sfx, SfxRequests};
+use crate::platforms::Platforms;
+use crate::abilities::Ability;
 
+#[derive(Clone)]
 pub struct Player {
     pub pos: cf32,
     pub vel: cf32,
     pub anim_timer: std::num::Wrapping<u8>,
 
-    pub power: f32,
-    pub jump_dir: Option<f32>,
+    pub power: Real,
+    pub jump_dir: Option<Real>,
     remembered_jump : u8,
     pub grounded: bool,
 
     /// To move better diagonally when we are on a slope
     pub ground_level: cf32,
-    ground_level_score: f32,
+    ground_level_score: Real,
 
     pub status: crate::TouchedUniqueItems,
+    pub visited: crate::VisitedRooms,
+    /// Index of the platform (in `World::platforms`) the player stands on
+    pub riding: Option<usize>,
+    /// Position of the last touched checkpoint, to respawn at
+    pub checkpoint: Option<TilePos>,
+    /// Frames `BUTTON_1` is being held, to tell opening the map from respawning
+    respawn_hold: u8,
+    respawn_requested: bool,
+    /// Sound effects caused by the player during this frame
+    pub sfx: SfxRequests,
+    /// Tile the player bounced off since this was last taken, for its animation
+    pub bounced: Option<TilePos>,
+
+    pub health: u8,
+    /// Frames left during which the player cannot be hurt again
+    invulnerable: u8,
+
+    /// Jumps left before landing, with `Ability::DoubleJump`
+    air_jumps: u8,
+    /// Horizontal direction away from a wall touched during the last frame
+    wall_normal: Option<Real>,
+    /// Holding on to a wall with `Ability::WallCling`
+    pub clinging: bool,
+    /// Falling fast after a ground pound, until landing
+    pub pounding: bool,
+    /// Standing on a one-way tile, so that pressing down drops through it
+    on_one_way: bool,
+    /// Frames left during which one-way tiles do not collide
+    dropping: u8,
+    /// Of the tile the player is in or stands on
+    pub physics: Physics,
 }
 
+pub const MAX_HEALTH: u8 = 3;
+const INVULNERABILITY_FRAMES: u8 = 90;
+/// Holding `BUTTON_1` that long respawns at the last checkpoint instead of opening the map
+const RESPAWN_HOLD_FRAMES: u8 = 60;
+/// Distance over which segments repel the player, beyond the radii
+const FEATHER: Real = real(3.0);
+/// Velocity of knockback when hurt
+const KNOCKBACK: Real = real(300.0);
+/// Downward velocity of a ground pound
+const POUND_SPEED: Real = real(500.0);
+/// Time to fall through a one-way tile after pressing down
+const DROP_FRAMES: u8 = 15;
+/// Most collision passes in a frame. Fast movement gets longer substeps instead of more of them.
+const MAX_SUBSTEPS: u32 = 40;
+/// Above that speed repelling forces may not stop the player within a tile, so substeps are checked for surfaces in the way
+const SWEEP_SPEED: Real = real(1000.0);
+
 impl Player {
     pub const fn new() -> Player {
         Player {
             pos: cf32::new(f32::NAN, f32::NAN),
             vel: cf32::new(0.0, 0.0),
-            power: 50.0,
+            power: real(50.0),
             anim_timer: std::num::Wrapping(0),
             grounded: false,
             jump_dir: None,
             remembered_jump: 0,
             ground_level: cf32::new(1.0, 0.0),
-            ground_level_score: 0.3,
+            ground_level_score: real(0.3),
             status: crate::TouchedUniqueItems::new(),
+            visited: crate::VisitedRooms::new(),
+            riding: None,
+            checkpoint: None,
+            respawn_hold: 0,
+            respawn_requested: false,
+            sfx: SfxRequests::new(),
+            bounced: None,
+            health: MAX_HEALTH,
+            invulnerable: 0,
+            air_jumps: 0,
+            wall_normal: None,
+            clinging: false,
+            pounding: false,
+            on_one_way: false,
+            dropping: 0,
+            physics: Physics::NORMAL,
         }
     }
     pub fn jump_strength(cur: u8) -> f32 {
@@ -50,12 +118,34 @@ impl Player {
     }
     pub fn control(&mut self, prev: u8, cur: u8) -> MainState {
         let mut dir = cf32::new(0.0, 0.0);
-        let mut movpower : f32 = 0.0;
+        let mut movpower = real(0.0);
 
-        if (cur & !prev) & BUTTON_1 != 0{
-            return MainState::Map;
+        // The press should start in the game, not in the map viewer closed by it
+        if cur & BUTTON_1 != 0 && (prev & BUTTON_1 == 0 || self.respawn_hold > 0) {
+            self.respawn_hold = self.respawn_hold.saturating_add(1);
+            if self.respawn_hold == RESPAWN_HOLD_FRAMES {
+                self.respawn_requested = true;
+            }
+        } else if cur & BUTTON_1 == 0 && self.respawn_hold > 0 {
+            let short_press = self.respawn_hold < RESPAWN_HOLD_FRAMES;
+            self.respawn_hold = 0;
+            if short_press {
+                return MainState::Map;
+            }
         }
 
+        if self.grounded {
+            self.air_jumps = if self.has(Ability::DoubleJump) { 1 } else { 0 };
+            self.pounding = false;
+        }
+        self.clinging = match self.wall_normal {
+            Some(normal) if !self.grounded && !self.pounding && self.has(Ability::WallCling) => {
+                let towards = if normal > 0.0 { BUTTON_LEFT } else { BUTTON_RIGHT };
+                cur & towards != 0
+            }
+            _ => false,
+        };
+
         let mut do_jump_now = false;
         if prev & BUTTON_2 != 0 {
             self.jump_dir = Some(self.jump_dir.unwrap_or_default());
@@ -66,9 +156,12 @@ impl Player {
             if cur & BUTTON_RIGHT != 0 {
                 *jump_dir += 0.03;
             }
-            *jump_dir = jump_dir.clamp(-1.0, 1.0);
+            *jump_dir = (*jump_dir).clamp(-1.0, 1.0);
             if cur & BUTTON_2 == 0  {
-                if self.grounded {
+                if self.grounded || self.clinging {
+                    do_jump_now = true;
+                } else if self.air_jumps > 0 {
+                    self.air_jumps -= 1;
                     do_jump_now = true;
                 } else {
                     self.remembered_jump = 12;
@@ -90,6 +183,13 @@ impl Player {
                 if cur & BUTTON_DOWN != 0 {
                     dir.im += 1.0;
                 }
+                if (cur & !prev) & BUTTON_DOWN != 0 && !self.pounding && !self.physics.swim && self.has(Ability::GroundPound) {
+                    self.pounding = true;
+                    self.vel = cf32::new(0.0, 1.0) * POUND_SPEED;
+                }
+                if self.pounding {
+                    dir = cf32::new(0.0, 0.0);
+                }
             } else {
                 // grounded
                 if cur & BUTTON_LEFT != 0 {
@@ -108,9 +208,12 @@ impl Player {
                 if cur & BUTTON_DOWN != 0 {
                     brake = true;
                 }
+                if (cur & !prev) & BUTTON_DOWN != 0 && self.on_one_way {
+                    self.dropping = DROP_FRAMES;
+                }
             }
 
-            movpower = if self.grounded {
+            movpower = if self.grounded || self.physics.swim {
                 self.power / 20.0
             } else {
                 self.power / 200.0
@@ -136,13 +239,21 @@ impl Player {
 
         if do_jump_now {
             if self.vel.im > 0.0 {
-                self.vel.im = 0.0;
+                self.vel.im = real(0.0);
+            }
+            dir = cf32::new(0.0, -1.0);
+            dir.re = self.jump_dir.unwrap();
+            if self.clinging {
+                // push off the wall
+                dir.re += self.wall_normal.unwrap_or_default();
+                self.clinging = false;
             }
-            dir = cf32::new(self.jump_dir.unwrap(), -1.0);
+            self.pounding = false;
             let strength = Player::jump_strength(cur);
             movpower = self.power * strength;
             self.jump_dir = None;
             self.remembered_jump = 0;
+            self.sfx.request(Sfx::Jump);
         }
 
         let dirnorm = dir.norm();
@@ -159,79 +270,43 @@ impl Player {
 
 
         self.power = 0.95*self.power + 0.05*300.0;
+        self.dropping = self.dropping.saturating_sub(1);
 
         if self.grounded && self.vel.re.abs() > 5.0 {
             self.anim_timer += std::num::Wrapping(1);
         }
         MainState::Game
     }
-    fn repel_tile(&mut self, tile_center: cf32, config: &[CollisionSegment], acceleration: &mut cf32) {
+    /// Returns whether the player stands on this tile
+    fn repel_tile(&mut self, tile_center: cf32, config: &[CollisionSegment], acceleration: &mut cf32) -> bool {
         const DEBUG_REPEL : bool = false;
-
-        if config.is_empty() {
-            return;
-        }
+        let mut grounding = false;
 
         if DEBUG_REPEL {
             crate::traceln!(
                 ":::: {} {}",
-                (self.pos.re * 10.0) as i32,
-                (self.pos.im * 10.0) as i32,
+                (self.pos.re * 10.0).to_f32() as i32,
+                (self.pos.im * 10.0).to_f32() as i32,
             );
         }
 
-        let mut attained_distance: f32 = f32::INFINITY;
-        // dummy initial values:
-        let mut chosen_vector: cf32 = cf32::new(f32::NAN, f32::NAN);
-        let mut chosen_vector_norm: f32 = f32::NAN;
-        let mut chosen_segment: &CollisionSegment = &config[0];
-
-
-        for colsegm in config {
-            let pos1 = tile_center + colsegm.rp1;
-            let pos2 = tile_center + colsegm.rp2;
-
-            let x;
-            let projected_forcepoint = {
-                let a = self.pos - pos1;
-                let b = pos2 - pos1;
-                x = (a / b).re.clamp(0.0, 1.0);
-                pos1 * (1.0 - x) + pos2 * x
-            };
-
-            let v =  self.pos - projected_forcepoint;
-            let vn = v.norm();
-            
-            if DEBUG_REPEL {
-                crate::traceln!(
-                    "cand @ {}  {} {}",
-                    (x*100.0) as i32,
-                    (projected_forcepoint.re * 10.0) as i32,
-                    (projected_forcepoint.im * 10.0) as i32,
-                );
-                crate::traceln!(
-                    "vector {} {} n={}",
-                    (v.re * 10.0) as i32,
-                    (v.im * 10.0) as i32,
-                    (   vn * 100.0) as i32
-                );
-            }
+        let (chosen_segment, mut chosen_vector) = match CollisionSegment::closest(config, tile_center, self.pos) {
+            Some(x) => x,
+            None => return false,
+        };
+        let chosen_vector_norm = chosen_vector.norm();
 
-            if attained_distance > vn + colsegm.rad {
-                chosen_vector = v;
-                chosen_vector_norm = vn;
-                chosen_segment = colsegm;
-                attained_distance = vn + colsegm.rad;
-                if DEBUG_REPEL { crate::traceln!("new cand",); }
-            }
+        if DEBUG_REPEL {
+            crate::traceln!(
+                "vector {} {} n={}",
+                (chosen_vector.re * 10.0).to_f32() as i32,
+                (chosen_vector.im * 10.0).to_f32() as i32,
+                (chosen_vector_norm * 100.0).to_f32() as i32
+            );
         }
 
-        let mut radius = 3.0;
-        if self.status.is_touched(crate::UniqueItem::FeatureSmallSize) {
-            radius = 1.5;
-        }
-        let feather = 3.0;
-        radius += chosen_segment.rad;
+        let feather = FEATHER;
+        let radius = self.radius() + chosen_segment.rad;
 
         if chosen_vector_norm < radius+feather {
             chosen_vector = chosen_vector.unscale(chosen_vector_norm);
@@ -243,13 +318,17 @@ impl Player {
             if DEBUG_REPEL {
                 crate::traceln!(
                     "within_range fade={}",
-                    (fade * 100.0) as i32,
+                    (fade * 100.0).to_f32() as i32,
                 );
-                crate::traceln!("accel={}", (accelerating*100.0) as i32 );
+                crate::traceln!("accel={}", (accelerating*100.0).to_f32() as i32 );
+            }
+            if fade > 0.01 && chosen_vector.re.abs() > 0.9 {
+                self.wall_normal = Some(chosen_vector.re.signum());
             }
             if fade > 0.01 && chosen_vector.im < -0.5 {
                 if DEBUG_REPEL { crate::traceln!("grounding",); }
                 self.grounded = true;
+                grounding = true;
 
                 let mut new_ground_level = chosen_segment.rp2 - chosen_segment.rp1;
                 new_ground_level = new_ground_level.unscale(new_ground_level.norm());
@@ -262,42 +341,49 @@ impl Player {
                     if DEBUG_REPEL { 
                         crate::traceln!(
                             "new grlvl {} {}",
-                            (100.0 * self.ground_level.re) as i32,
-                            (100.0 * self.ground_level.im) as i32,
+                            (100.0 * self.ground_level.re).to_f32() as i32,
+                            (100.0 * self.ground_level.im).to_f32() as i32,
                         ); 
                     }
                 }
             }
 
             //traceln!("fade {}", (fade*100.0) as i32);
-            let mut scale = if chosen_vector_norm <= radius { 1.0 } else { fade * fade  };
+            let mut scale = if chosen_vector_norm <= radius { real(1.0) } else { fade * fade  };
             scale *= 200.0;
             if accelerating > 0.01 {
                 scale *= accelerating; // prevent lateral forces
                 scale *= chosen_segment.el;
                 if scale < 0.3*chosen_segment.el {
-                    scale = 0.0;
+                    scale = real(0.0);
+                } else if chosen_segment.el > 0.5 && self.vel.norm() > 100.0 {
+                    self.sfx.request(Sfx::Bounce);
+                    self.bounced = Some(World::to_world_coords(tile_center));
                 }
             } else if accelerating < -0.02 {
                 scale *= -accelerating; // prevent lateral forces
                 if scale < 0.01 {
-                    scale = 0.0;
+                    scale = real(0.0);
                 }
             }
 
             if DEBUG_REPEL {
                 crate::traceln!(
                     "act dir=({} {}) scale={}",
-                    (chosen_vector.re * 10.0) as i32,
-                    (chosen_vector.im * 10.0) as i32,
-                    (scale * 100.0) as i32
+                    (chosen_vector.re * 10.0).to_f32() as i32,
+                    (chosen_vector.im * 10.0).to_f32() as i32,
+                    (scale * 100.0).to_f32() as i32
                 );
             }
             *acceleration += chosen_vector.scale(scale);
         }
+        grounding
     }
-    pub fn handle_collisions(&mut self, acceleration: &mut cf32 ) {
-        self.ground_level_score = 0.3; // do not touch ground level if it is detected this steep;
+    pub fn handle_collisions(&mut self, platforms: &Platforms, acceleration: &mut cf32 ) {
+        self.ground_level_score = real(0.3); // do not touch ground level if it is detected this steep;
+        self.wall_normal = None;
+        self.on_one_way = false;
+        self.physics = World::get_tile(self.my_world_coords()).physics();
         //rp(cf32::new(70.0, 100.0));
         //return;
 
@@ -306,26 +392,63 @@ impl Player {
         let xx = myx.saturating_sub(1);
         let yy = myy.saturating_sub(1);
 
+        // including the tile the player is in, which can have a surface in it, e.g. a slope
         for y in yy..(yy+3) {
             for x in xx..(xx+3) {
-                if x == myx && y == myy { continue }
                 let tiletype = World::get_tile((x, y));
-                self.repel_tile(World::from_world_coords((x,y)), tiletype.collision_configuration(), acceleration);
+                let center = World::from_world_coords((x,y));
+                let config = tiletype.collision_configuration();
+                if tiletype.one_way() && !self.lands_on(center, config) {
+                    continue;
+                }
+                if self.repel_tile(center, config, acceleration) {
+                    self.on_one_way |= tiletype.one_way();
+                    if tiletype.physics() != Physics::NORMAL {
+                        self.physics = tiletype.physics();
+                    }
+                }
+            }
+        }
+
+        let mut riding = None;
+        platforms.tiles_near(self.pos, 16.0, |i, center, config| {
+            if self.repel_tile(center, config, acceleration) {
+                riding = Some(i);
             }
+        });
+        if riding.is_some() {
+            self.riding = riding;
+        }
+    }
+    /// Whether a one-way tile should stop the player: when coming from above and not moving up or dropping through
+    fn lands_on(&self, tile_center: cf32, config: &[CollisionSegment]) -> bool {
+        // small upward speed comes from being pushed out of the tile while standing on it
+        if self.dropping > 0 || self.vel.im < -50.0 {
+            return false;
+        }
+        match CollisionSegment::closest(config, tile_center, self.pos) {
+            Some((_, v)) => v.im < 0.0,
+            None => false,
         }
     }
     pub fn movement(&mut self, acceleration: &mut cf32) {
-        *acceleration += cf32::new(0.0, 0.5);
+        if self.clinging {
+            // hold still on the wall instead of falling
+            acceleration.im -= self.vel.im * 0.05;
+        } else {
+            *acceleration += cf32::new(0.0, 0.5) * self.physics.gravity;
+        }
+        *acceleration += self.physics.push;
 
         if self.grounded {
             // friction
-            *acceleration -= cf32::new(self.vel.re * 0.002, 0.0);
+            acceleration.re -= self.vel.re * 0.002;
             if self.vel.re.abs() < 0.001 {
-                self.vel.re = 0.0;
+                self.vel.re = real(0.0);
             }
         }
 
-        *acceleration -= self.vel / 2000.0;
+        *acceleration -= self.vel * self.physics.drag;
          
         /*
         if self.pos.re < 4.0 {
@@ -346,21 +469,123 @@ impl Player {
         }
         */
     }
+    /// Integrate movement over one frame, in substeps with collision handling in each of them
+    pub fn simulate_frame(&mut self, platforms: &Platforms) {
+        let was_grounded = self.grounded;
+        let fall_speed = self.vel.im;
+
+        if let Some(Some(platform)) = self.riding.map(|i| World::platforms()[i]) {
+            self.pos += platforms.displacement(&platform);
+        }
+        self.riding = None;
+
+        let mut iterations_counter = 0;
+        let mut remaining_movement_units = real(10.0);
+        while remaining_movement_units > 0.0 {
+            self.grounded = false;
+            
+            //self.ground_force_direction += cf32::new(0.0, -0.02);
+            
+            let mut acceleration = cf32::new(0.0, 0.0);
+            self.handle_collisions(platforms, &mut acceleration);
+            self.movement(&mut acceleration);
+            
+            let vel_estimate1 = self.vel.norm();
+            let vel_estimate2 = (self.vel + acceleration*2.0).norm();
+            let vel_estimate = vel_estimate1.max(vel_estimate2  ) / 2000.0;
+
+            // aim to move do 5 iterations of collision calculations per pixel of movement
+            let mut epsilon = 0.2 / vel_estimate;
+
+            epsilon = epsilon.min(1.0/acceleration.norm());
+            epsilon = epsilon.max(0.1);
+            // spread the rest of the frame over the remaining substeps
+            epsilon = epsilon.max(remaining_movement_units / (MAX_SUBSTEPS - iterations_counter) as f32);
+            epsilon = epsilon.min(remaining_movement_units);
+
+            //crate::traceln!("  accel {} epsilon {}", (acceleration.norm() * 100.0) as i32, (epsilon * 100.0) as i32);
+
+            self.vel += epsilon * acceleration;
+            self.move_by(platforms, epsilon * self.vel / 2000.0);
+
+            remaining_movement_units -= epsilon;
+            iterations_counter += 1;
+        }
+        //crate::traceln!("iters {}", iterations_counter);
+
+        if self.grounded && !was_grounded && fall_speed > 150.0 {
+            self.sfx.request(Sfx::Land);
+        }
+    }
+    /// Move by `delta`, stopping at the first surface in the way and losing the velocity into it, except for bouncing
+    /// off elastic segments. At lower speeds this is left to the repelling forces of `handle_collisions`.
+    fn move_by(&mut self, platforms: &Platforms, delta: cf32) {
+        let length = delta.norm();
+        if self.vel.norm() < SWEEP_SPEED || length == 0.0 {
+            self.pos += delta;
+            return;
+        }
+        let dir = delta.unscale(length);
+        let radius = self.radius();
+        let dropping = self.dropping > 0;
+
+        let mut first: Option<(Real, cf32, f32)> = None;
+        let mut check = |center: cf32, config: &[CollisionSegment], one_way: bool| {
+            if let Some((segm, s, normal)) = CollisionSegment::sweep(config, center, self.pos, dir, length, radius) {
+                // one-way tiles only stop what comes from above
+                if one_way && (dropping || normal.im >= 0.0) {
+                    return;
+                }
+                if first.is_none_or(|(attained, _, _)| s < attained) {
+                    first = Some((s, normal, segm.el));
+                }
+            }
+        };
+
+        let (x1, y1) = World::to_world_coords(self.pos);
+        let (x2, y2) = World::to_world_coords(self.pos + delta);
+        for y in y1.min(y2).saturating_sub(1)..=y1.max(y2) + 1 {
+            for x in x1.min(x2).saturating_sub(1)..=x1.max(x2) + 1 {
+                let tiletype = World::get_tile((x, y));
+                check(World::from_world_coords((x, y)), tiletype.collision_configuration(), tiletype.one_way());
+            }
+        }
+        platforms.tiles_near(self.pos, length.to_f32() + 16.0, |_, center, config| check(center, config, false));
+
+        match first {
+            Some((s, normal, el)) => {
+                self.pos += dir.scale(s);
+                let into = (self.vel / normal).re;
+                if into < 0.0 {
+                    self.vel -= normal.scale(into * (1.0 + el));
+                }
+            }
+            None => self.pos += delta,
+        }
+    }
     pub fn draw(&self, _global_frame: u8, keys: u8, cam: &Camera) {
+        let onscreen = cam.to_screen(self.pos);
+        // shrinking bar until respawn
+        if self.respawn_hold > 10 && self.respawn_hold < RESPAWN_HOLD_FRAMES {
+            draw_colours(2, 0, 0, 0);
+            let half = (RESPAWN_HOLD_FRAMES - self.respawn_hold) as i32 / 4;
+            line(onscreen.re.to_f32() as i32 - half, onscreen.im.to_f32() as i32 - 10, onscreen.re.to_f32() as i32 + half, onscreen.im.to_f32() as i32 - 10);
+        }
+        // blink while invulnerable
+        if self.invulnerable & 0b100 != 0 {
+            return;
+        }
         draw_colours(3, 0, 0, 0);
-        let onscreen = self.pos - cam.pos + cf32::new(0.5, 0.5) * SCREEN_SIZE as f32;
-        if self.status.is_touched(crate::UniqueItem::FeatureSmallSize) {
+        if self.has(Ability::SmallSize) {
             if self.anim_timer.0 & 0x1F < 16 {
-                blit(&sprites::WHEEL_S, onscreen.re as i32 - 4, onscreen.im as i32 - 4, 8, 8, BLIT_1BPP);
+                blit(&sprites::WHEEL_S, onscreen.re.to_f32() as i32 - 4, onscreen.im.to_f32() as i32 - 4, 8, 8, BLIT_1BPP);
             } else {
-                blit(&sprites::WHEEL_S, onscreen.re as i32 - 4, onscreen.im as i32 - 4, 8, 8, BLIT_1BPP|BLIT_FLIP_X);
+                blit(&sprites::WHEEL_S, onscreen.re.to_f32() as i32 - 4, onscreen.im.to_f32() as i32 - 4, 8, 8, BLIT_1BPP|BLIT_FLIP_X);
             }
         } else {
-            if self.anim_timer.0 & 0x1F < 16 {
-                blit(&sprites::WHEEL1, onscreen.re as i32 - 8, onscreen.im as i32 - 8, 16, 16, BLIT_1BPP);
-            } else {
-                blit(&sprites::WHEEL2, onscreen.re as i32 - 8, onscreen.im as i32 - 8, 16, 16, BLIT_1BPP);
-            };
+            draw_colours(0, 2, 4, 3);
+            let frame = if self.anim_timer.0 & 0x1F < 16 { 0 } else { 1 };
+            sprites::WHEEL_SHEET.blit(frame, onscreen.re.to_f32() as i32 - 8, onscreen.im.to_f32() as i32 - 8, 0);
         }
         if let Some(jump_dir) = self.jump_dir {
             if self.grounded {
@@ -368,17 +593,93 @@ impl Player {
             } else {
                 draw_colours(2, 0, 0, 0);
             }
-            let mut v = cf32::new(jump_dir, -1.0);
+            let mut v = cf32::new(0.0, -1.0);
+            v.re = jump_dir;
             v = v.unscale(v.norm());
             let strength = Player::jump_strength(keys);
             v = onscreen + v * 1.0 * ((2.0*strength).exp()*2.0 - 0.5); 
-            line(onscreen.re as i32 , onscreen.im as i32, v.re as i32, v.im as i32)
+            line(onscreen.re.to_f32() as i32 , onscreen.im.to_f32() as i32, v.re.to_f32() as i32, v.im.to_f32() as i32)
         }
         if self.grounded {
             draw_colours(4, 0, 0, 0);
             let p1 = onscreen - self.ground_level*2.0 + cf32::new(0.0, 4.0);
             let p2 = onscreen + self.ground_level*2.0 + cf32::new(0.0, 4.0);
-            line(p1.re as i32, p1.im as i32, p2.re as i32, p2.im as i32);
+            line(p1.re.to_f32() as i32, p1.im.to_f32() as i32, p2.re.to_f32() as i32, p2.im.to_f32() as i32);
+        }
+    }
+
+    /// Hazardous tile around the player that is touched, i.e. close enough to repel it
+    pub fn touching_hazard(&self) -> Option<cf32> {
+        let (myx,myy) = self.my_world_coords();
+        for y in myy.saturating_sub(1)..(myy+2) {
+            for x in myx.saturating_sub(1)..(myx+2) {
+                let tiletype = World::get_tile((x, y));
+                let center = World::from_world_coords((x,y));
+                if tiletype.hurts() && CollisionSegment::touches(tiletype.collision_configuration(), center, self.pos, self.radius() + FEATHER) {
+                    return Some(center);
+                }
+            }
+        }
+        None
+    }
+
+    /// Take a hit from something at `source`, unless hit recently. Returns whether no health is left.
+    pub fn hurt(&mut self, source: cf32) -> bool {
+        if self.invulnerable > 0 {
+            return false;
+        }
+        self.health = self.health.saturating_sub(1);
+        self.invulnerable = INVULNERABILITY_FRAMES;
+        let mut away = self.pos - source;
+        if away.norm() > 0.1 {
+            away = away.unscale(away.norm());
+        }
+        self.vel = (away + cf32::new(0.0, -1.0)).scale(KNOCKBACK);
+        self.jump_dir = None;
+        self.remembered_jump = 0;
+        self.sfx.request(Sfx::Hurt);
+        self.health == 0
+    }
+
+    /// Whether respawn was requested by holding `BUTTON_1`, clearing the request
+    pub fn take_respawn_request(&mut self) -> bool {
+        std::mem::take(&mut self.respawn_requested)
+    }
+
+    /// Move to the given tile, at rest
+    pub fn respawn(&mut self, pos: TilePos) {
+        self.pos = World::from_world_coords(pos);
+        self.vel = cf32::new(0.0, 0.0);
+        self.jump_dir = None;
+        self.remembered_jump = 0;
+        self.riding = None;
+        self.clinging = false;
+        self.pounding = false;
+        self.dropping = 0;
+    }
+
+    /// Restore full health
+    pub fn revive(&mut self) {
+        self.health = MAX_HEALTH;
+        self.invulnerable = 0;
+    }
+
+    /// Count down invulnerability after a hit
+    pub fn recover(&mut self) {
+        self.invulnerable = self.invulnerable.saturating_sub(1);
+    }
+
+    /// Whether the Feature item unlocking the ability has been touched
+    pub fn has(&self, ability: Ability) -> bool {
+        self.status.is_touched(ability.feature())
+    }
+
+    /// Collision radius of the wheel
+    pub fn radius(&self) -> Real {
+        if self.has(Ability::SmallSize) {
+            real(1.5)
+        } else {
+            real(3.0)
         }
     }
 
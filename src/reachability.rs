@@ -0,0 +1,149 @@
+//! Offline check that every unique item of the level can actually be collected.
+//!
+//! Runs the real `Player` physics from `UniqueItem::PlayerStart` over a breadth-first search of input sequences
+//! (walks and aimed jumps with air control), deduplicating similar states, and reports which items were never touched.
+//! Moving platforms are simulated along with each state, but the platform phase is not part of the deduplication key,
+//! so rides that need a particular timing may be missed.
+//! Enemies and hazards are ignored: an item counts as reachable even if getting there costs health.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use enum_iterator::IntoEnumIterator;
+
+use crate::{TilePos, UniqueItem, abilities::Ability, platforms::Platforms, player::Player, world::World};
+use crate::wasm4::{BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP};
+
+/// Frames to wait after an action for the player to come to rest
+const SETTLE_FRAMES: u32 = 120;
+
+/// Sequence of `(gamepad, number of frames)`
+type Action = Vec<(u8, u32)>;
+
+fn actions() -> Vec<Action> {
+    let mut actions = vec![
+        vec![(BUTTON_LEFT, 12), (0, SETTLE_FRAMES)],
+        vec![(BUTTON_RIGHT, 12), (0, SETTLE_FRAMES)],
+        // drop through a one-way tile
+        vec![(BUTTON_DOWN, 1), (0, SETTLE_FRAMES)],
+    ];
+    // `jump_dir` changes by 0.03 per frame of aiming
+    for &(aim_key, aim_frames) in &[(BUTTON_LEFT, 34), (BUTTON_LEFT, 17), (0, 0), (BUTTON_RIGHT, 17), (BUTTON_RIGHT, 34)] {
+        for &strength_key in &[BUTTON_UP, BUTTON_DOWN] {
+            for &air_key in &[0, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, BUTTON_UP | BUTTON_LEFT, BUTTON_UP | BUTTON_RIGHT] {
+                actions.push(vec![
+                    (BUTTON_2, 1),
+                    (BUTTON_2 | aim_key, aim_frames),
+                    (strength_key, 1),
+                    (air_key, 60),
+                    (0, SETTLE_FRAMES),
+                ]);
+            }
+        }
+    }
+    actions
+}
+
+/// Only states of player standing still are queued, so tile and unlocked abilities describe them well enough
+fn state_key(player: &Player) -> (TilePos, [bool; Ability::ALL.len()]) {
+    (player.my_world_coords(), Ability::ALL.map(|a| player.has(a)))
+}
+
+/// Standing still with jump power recharged, so that any action can be tried from here
+fn is_settled(player: &Player) -> bool {
+    player.grounded && player.jump_dir.is_none() && player.vel.norm_sqr() < 100.0 && player.power > 290.0
+}
+
+pub struct Report {
+    /// Items touched in some explored state
+    pub reachable: HashSet<UniqueItem>,
+    /// Items touched only after `FeatureSmallSize` has been collected
+    pub need_small_size: HashSet<UniqueItem>,
+    pub explored_states: usize,
+}
+
+impl Report {
+    pub fn unreachable(&self) -> Vec<UniqueItem> {
+        UniqueItem::into_enum_iter().filter(|i| !self.reachable.contains(i)).collect()
+    }
+}
+
+/// Explore up to `max_states` states
+pub fn analyze(max_states: usize) -> Report {
+    let item_positions: HashMap<TilePos, UniqueItem> = UniqueItem::into_enum_iter().map(|i| (i.get_pos(), i)).collect();
+    let (max_x, max_y) = World::BOTTOM_RIGHT_TILE;
+    let actions = actions();
+
+    let mut report = Report {
+        reachable: HashSet::new(),
+        need_small_size: HashSet::new(),
+        explored_states: 0,
+    };
+    let mut reached_while_big = HashSet::new();
+
+    let mut start = Player::new();
+    let mut platforms = Platforms::new();
+    start.pos = World::from_world_coords(UniqueItem::PlayerStart.get_pos());
+    for _ in 0..SETTLE_FRAMES {
+        start.control(0, 0);
+        platforms.tick();
+        start.simulate_frame(&platforms);
+    }
+
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    queue.push_back((start, platforms));
+
+    while let Some((state, platforms)) = queue.pop_front() {
+        if report.explored_states >= max_states {
+            break;
+        }
+        report.explored_states += 1;
+
+        'actions: for action in &actions {
+            let mut player = state.clone();
+            let mut platforms = platforms.clone();
+            let mut prev = 0;
+            for &(gamepad, frames) in action {
+                for _ in 0..frames {
+                    player.control(prev, gamepad);
+                    platforms.tick();
+                    player.simulate_frame(&platforms);
+                    prev = gamepad;
+
+                    if !player.pos.is_normal() || player.pos.re < 0.0 || player.pos.im < 0.0
+                        || player.pos.re > 8.0 * max_x as f32 || player.pos.im > 8.0 * max_y as f32 {
+                        continue 'actions;
+                    }
+
+                    if let Some(&item) = item_positions.get(&player.my_world_coords()) {
+                        player.status.touch(item);
+                        report.reachable.insert(item);
+                        if !player.status.is_touched(UniqueItem::FeatureSmallSize) || item == UniqueItem::FeatureSmallSize {
+                            reached_while_big.insert(item);
+                        }
+                    }
+
+                    if gamepad == 0 && is_settled(&player) {
+                        break;
+                    }
+                }
+            }
+            if is_settled(&player) && visited.insert(state_key(&player)) {
+                queue.push_back((player, platforms));
+            }
+        }
+    }
+
+    report.need_small_size = report.reachable.difference(&reached_while_big).copied().collect();
+    report
+}
+
+#[test]
+fn every_unique_item_is_reachable() {
+    let report = analyze(5000);
+    for item in &report.need_small_size {
+        eprintln!("{} at {:?} is reachable only after {}", item.name(), item.get_pos(), UniqueItem::FeatureSmallSize.name());
+    }
+    let unreachable: Vec<_> = report.unreachable().iter().map(|i| (i.name(), i.get_pos())).collect();
+    assert!(unreachable.is_empty(), "unreachable items after exploring {} states: {:?}", report.explored_states, unreachable);
+}
@@ -0,0 +1,230 @@
+//! Recording and replaying of gamepad input.
+//!
+//! `GlobalState::tick` depends only on the gamepad byte of each frame, so input stream alone reproduces the whole run.
+//! Recordings are run-length encoded as pairs of bytes: gamepad state and number of frames (1..=255) it was held for.
+
+use crate::{Scalar, GlobalState, cf32, player::Player};
+
+pub struct InputRecorder<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    next_frame: u32,
+}
+
+impl<const N: usize> InputRecorder<N> {
+    pub const fn new() -> Self {
+        InputRecorder {
+            buf: [0; N],
+            len: 0,
+            next_frame: 0,
+        }
+    }
+
+    /// Append gamepad state of a frame. Frames skipped since the previous call are recorded as holding the previous state.
+    /// Returns `false` if the buffer is full or the frame was already recorded.
+    pub fn record(&mut self, frame: u32, gamepad: u8) -> bool {
+        if frame < self.next_frame {
+            return false;
+        }
+        let previous = if self.len >= 2 { self.buf[self.len-2] } else { 0 };
+        while self.next_frame < frame {
+            if !self.push(previous) {
+                return false;
+            }
+        }
+        self.push(gamepad)
+    }
+
+    fn push(&mut self, gamepad: u8) -> bool {
+        if self.len >= 2 && self.buf[self.len-2] == gamepad && self.buf[self.len-1] < u8::MAX {
+            self.buf[self.len-1] += 1;
+        } else {
+            if self.len + 2 > N {
+                return false;
+            }
+            self.buf[self.len] = gamepad;
+            self.buf[self.len+1] = 1;
+            self.len += 2;
+        }
+        self.next_frame += 1;
+        true
+    }
+
+    /// Number of frames recorded so far
+    pub fn frames(&self) -> u32 {
+        self.next_frame
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl<const N: usize> Default for InputRecorder<N> {
+    fn default() -> Self {
+        InputRecorder::new()
+    }
+}
+
+/// Iterator over per-frame gamepad states of a recording
+pub struct InputReplay<'a> {
+    data: &'a [u8],
+    frames_left_in_run: u8,
+}
+
+impl<'a> InputReplay<'a> {
+    pub fn new(recording: &'a [u8]) -> InputReplay<'a> {
+        InputReplay {
+            data: recording,
+            frames_left_in_run: recording.get(1).copied().unwrap_or(0),
+        }
+    }
+}
+
+impl<'a> Iterator for InputReplay<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        while self.frames_left_in_run == 0 {
+            if self.data.len() < 4 {
+                return None;
+            }
+            self.data = &self.data[2..];
+            self.frames_left_in_run = self.data[1];
+        }
+        self.frames_left_in_run -= 1;
+        Some(self.data[0])
+    }
+}
+
+/// Player state after a frame, for comparing runs against each other
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TrajectoryPoint {
+    pub frame: u32,
+    pub pos: cf32,
+    pub vel: cf32,
+    pub grounded: bool,
+}
+
+impl TrajectoryPoint {
+    pub fn of(frame: u32, player: &Player) -> TrajectoryPoint {
+        TrajectoryPoint {
+            frame,
+            pos: player.pos,
+            vel: player.vel,
+            grounded: player.grounded,
+        }
+    }
+
+    /// Print the point to debug console, in tenths of pixel
+    pub fn trace(&self) {
+        crate::traceln!(
+            "{} p {} {} v {} {} {}",
+            self.frame,
+            (self.pos.re * 10.0).to_f32() as i32,
+            (self.pos.im * 10.0).to_f32() as i32,
+            (self.vel.re * 10.0).to_f32() as i32,
+            (self.vel.im * 10.0).to_f32() as i32,
+            if self.grounded { "g" } else { "-" },
+        );
+    }
+}
+
+/// Feed a recording into the game frame by frame, reporting player state after each frame
+pub fn replay(state: &mut GlobalState, recording: &[u8], mut observer: impl FnMut(TrajectoryPoint)) {
+    for (frame, gamepad) in InputReplay::new(recording).enumerate() {
+        state.tick(gamepad);
+        observer(TrajectoryPoint::of(frame as u32, &state.game.player));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::wasm4::{self, BUTTON_2, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP};
+
+    #[test]
+    fn rle_roundtrip() {
+        let input: Vec<u8> = [0u8; 300].iter().copied()
+            .chain([BUTTON_RIGHT; 3])
+            .chain([BUTTON_RIGHT | BUTTON_2; 2])
+            .chain([0])
+            .collect();
+        let mut recorder = InputRecorder::<32>::new();
+        for (frame, &gamepad) in input.iter().enumerate() {
+            assert!(recorder.record(frame as u32, gamepad));
+        }
+        assert_eq!(recorder.as_bytes(), &[0, 255, 0, 45, BUTTON_RIGHT, 3, BUTTON_RIGHT | BUTTON_2, 2, 0, 1]);
+        assert_eq!(InputReplay::new(recorder.as_bytes()).collect::<Vec<_>>(), input);
+    }
+
+    #[test]
+    fn skipped_frames_hold_previous_state() {
+        let mut recorder = InputRecorder::<8>::new();
+        assert!(recorder.record(2, BUTTON_LEFT));
+        assert!(recorder.record(5, 0));
+        assert!(!recorder.record(5, 0));
+        assert_eq!(recorder.frames(), 6);
+        assert_eq!(recorder.as_bytes(), &[0, 2, BUTTON_LEFT, 3, 0, 1]);
+    }
+
+    #[test]
+    fn full_buffer() {
+        let mut recorder = InputRecorder::<2>::new();
+        assert!(recorder.record(0, 0));
+        assert!(!recorder.record(1, BUTTON_UP));
+    }
+
+    fn scripted_input(frame: u32) -> u8 {
+        match frame {
+            0..=59 => 0,
+            60..=99 => BUTTON_LEFT,
+            100..=129 => BUTTON_2 | BUTTON_LEFT,
+            130..=131 => BUTTON_UP,
+            132..=199 => BUTTON_RIGHT,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn replay_reproduces_trajectory() {
+        let _guard = wasm4::exclusive();
+
+        let mut recorder = InputRecorder::<64>::new();
+        let mut state = GlobalState::new();
+        let mut live = vec![];
+        for frame in 0..300 {
+            let gamepad = scripted_input(frame);
+            assert!(recorder.record(frame, gamepad));
+            state.tick(gamepad);
+            live.push(TrajectoryPoint::of(frame, &state.game.player));
+        }
+        assert!(live.iter().any(|p| !p.grounded));
+
+        for _ in 0..2 {
+            wasm4::reset();
+            let mut replayed = vec![];
+            replay(&mut GlobalState::new(), recorder.as_bytes(), |p| replayed.push(p));
+            assert_eq!(replayed, live);
+        }
+    }
+
+    /// Compared bit by bit, as fixed-point results must not depend on anything but the input
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn fixed_point_trajectories_are_identical() {
+        let _guard = wasm4::exclusive();
+        let run = || {
+            wasm4::reset();
+            let mut state = GlobalState::new();
+            (0..300).map(|frame| {
+                state.tick(scripted_input(frame));
+                let p = &state.game.player;
+                [p.pos.re, p.pos.im, p.vel.re, p.vel.im].map(|x| x.to_raw())
+            }).collect::<Vec<_>>()
+        };
+        let first = run();
+        assert!(first.windows(2).any(|w| w[0] != w[1]));
+        assert_eq!(first, run());
+    }
+}
@@ -0,0 +1,161 @@
+//! Persistent progress stored using WASM-4 `diskr`/`diskw`.
+//!
+//! Layout of the blob:
+//!
+//! * version byte - [`SAVE_VERSION`]
+//! * number of unique items in the game, to reject saves made with different item set
+//! * touched unique items bitfield
+//! * visited rooms bitfield
+//! * checkpoint position: x and y as little-endian u16, `0xFFFF` for both means "no checkpoint"
+//! * frame counter: little-endian u32
+//! * Fletcher-16 checksum of all preceding bytes, little-endian
+
+use crate::{TilePos, TouchedUniqueItems, UniqueItem, VisitedRooms, wasm4::{diskr, diskw}};
+
+/// Increment this each time meaning or layout of the save blob changes
+pub const SAVE_VERSION: u8 = 4;
+
+const STATUS_OFFSET: usize = 2;
+const VISITED_OFFSET: usize = STATUS_OFFSET + TouchedUniqueItems::BYTES;
+const CHECKPOINT_OFFSET: usize = VISITED_OFFSET + VisitedRooms::BYTES;
+const FRAMES_OFFSET: usize = CHECKPOINT_OFFSET + 4;
+const CHECKSUM_OFFSET: usize = FRAMES_OFFSET + 4;
+pub const SAVE_SIZE: usize = CHECKSUM_OFFSET + 2;
+
+const NO_CHECKPOINT: u16 = 0xFFFF;
+
+#[derive(Clone, Copy)]
+pub struct SaveData {
+    pub status: TouchedUniqueItems,
+    pub visited: VisitedRooms,
+    /// Position of the last touched checkpoint. Player reappears there after reload.
+    pub checkpoint: Option<TilePos>,
+    pub frames: u32,
+}
+
+const fn fletcher16(data: &[u8]) -> u16 {
+    let mut sum1: u16 = 0;
+    let mut sum2: u16 = 0;
+    let mut i = 0;
+    while i < data.len() {
+        sum1 = (sum1 + data[i] as u16) % 255;
+        sum2 = (sum2 + sum1) % 255;
+        i += 1;
+    }
+    (sum2 << 8) | sum1
+}
+
+impl SaveData {
+    pub fn serialize(&self) -> [u8; SAVE_SIZE] {
+        let mut buf = [0u8; SAVE_SIZE];
+        buf[0] = SAVE_VERSION;
+        buf[1] = UniqueItem::VARIANT_COUNT as u8;
+        buf[STATUS_OFFSET..VISITED_OFFSET].copy_from_slice(self.status.as_bytes());
+        buf[VISITED_OFFSET..CHECKPOINT_OFFSET].copy_from_slice(self.visited.as_bytes());
+
+        let (x, y) = self.checkpoint.unwrap_or((NO_CHECKPOINT, NO_CHECKPOINT));
+        buf[CHECKPOINT_OFFSET..CHECKPOINT_OFFSET+2].copy_from_slice(&x.to_le_bytes());
+        buf[CHECKPOINT_OFFSET+2..FRAMES_OFFSET].copy_from_slice(&y.to_le_bytes());
+        buf[FRAMES_OFFSET..CHECKSUM_OFFSET].copy_from_slice(&self.frames.to_le_bytes());
+
+        let checksum = fletcher16(&buf[..CHECKSUM_OFFSET]);
+        buf[CHECKSUM_OFFSET..].copy_from_slice(&checksum.to_le_bytes());
+        buf
+    }
+
+    /// Returns `None` for blobs of a different version or with a bad checksum
+    pub fn deserialize(buf: &[u8; SAVE_SIZE]) -> Option<SaveData> {
+        if buf[0] != SAVE_VERSION || buf[1] != UniqueItem::VARIANT_COUNT as u8 {
+            return None;
+        }
+        let checksum = u16::from_le_bytes([buf[CHECKSUM_OFFSET], buf[CHECKSUM_OFFSET+1]]);
+        if checksum != fletcher16(&buf[..CHECKSUM_OFFSET]) {
+            return None;
+        }
+
+        let mut status = [0u8; TouchedUniqueItems::BYTES];
+        status.copy_from_slice(&buf[STATUS_OFFSET..VISITED_OFFSET]);
+        let mut visited = [0u8; VisitedRooms::BYTES];
+        visited.copy_from_slice(&buf[VISITED_OFFSET..CHECKPOINT_OFFSET]);
+
+        let x = u16::from_le_bytes([buf[CHECKPOINT_OFFSET], buf[CHECKPOINT_OFFSET+1]]);
+        let y = u16::from_le_bytes([buf[CHECKPOINT_OFFSET+2], buf[CHECKPOINT_OFFSET+3]]);
+        let checkpoint = if (x, y) == (NO_CHECKPOINT, NO_CHECKPOINT) { None } else { Some((x, y)) };
+
+        let frames = u32::from_le_bytes([
+            buf[FRAMES_OFFSET], buf[FRAMES_OFFSET+1], buf[FRAMES_OFFSET+2], buf[FRAMES_OFFSET+3],
+        ]);
+
+        Some(SaveData {
+            status: TouchedUniqueItems::from_bytes(status),
+            visited: VisitedRooms::from_bytes(visited),
+            checkpoint,
+            frames,
+        })
+    }
+
+    /// Read saved progress from disk. Missing, outdated or corrupt saves yield `None`, meaning a fresh game.
+    pub fn load() -> Option<SaveData> {
+        let mut buf = [0u8; SAVE_SIZE];
+        let len = unsafe { diskr(buf.as_mut_ptr(), SAVE_SIZE as u32) };
+        if len as usize != SAVE_SIZE {
+            return None;
+        }
+        SaveData::deserialize(&buf)
+    }
+
+    pub fn store(&self) {
+        let buf = self.serialize();
+        unsafe {
+            diskw(buf.as_ptr(), SAVE_SIZE as u32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SaveData {
+        let mut status = TouchedUniqueItems::new();
+        status.touch(UniqueItem::InfoWelcome);
+        let mut visited = VisitedRooms::new();
+        visited.visit((20, 40));
+        SaveData { status, visited, checkpoint: Some((12, 34)), frames: 0x12345678 }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let restored = SaveData::deserialize(&sample().serialize()).unwrap();
+        assert!(restored.status.is_touched(UniqueItem::InfoWelcome));
+        assert!(!restored.status.is_touched(UniqueItem::PlayerStart));
+        assert!(restored.visited.is_visited((17, 33)));
+        assert!(!restored.visited.is_visited((20, 20)));
+        assert_eq!(restored.checkpoint, Some((12, 34)));
+        assert_eq!(restored.frames, 0x12345678);
+    }
+
+    #[test]
+    fn no_checkpoint() {
+        let data = SaveData { checkpoint: None, ..sample() };
+        assert_eq!(SaveData::deserialize(&data.serialize()).unwrap().checkpoint, None);
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        for i in 0..SAVE_SIZE {
+            let mut buf = sample().serialize();
+            buf[i] ^= 0x10;
+            assert!(SaveData::deserialize(&buf).is_none(), "flipped bit in byte {}", i);
+        }
+    }
+
+    #[test]
+    fn other_version_is_rejected() {
+        let mut buf = sample().serialize();
+        buf[0] = SAVE_VERSION - 1;
+        let checksum = fletcher16(&buf[..CHECKSUM_OFFSET]);
+        buf[CHECKSUM_OFFSET..].copy_from_slice(&checksum.to_le_bytes());
+        assert!(SaveData::deserialize(&buf).is_none());
+    }
+}
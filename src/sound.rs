@@ -0,0 +1,192 @@
+//! Sound effects played with `tone`.
+//!
+//! Game code requests effects as things happen, possibly several times per frame. Once per frame `Sounds::update` picks
+//! the highest priority request for each channel and starts it, unless a higher priority effect is still playing there.
+
+use crate::music::MusicPlayer;
+use crate::wasm4::{tone, TONE_MODE2, TONE_MODE3, TONE_NOISE, TONE_PULSE1, TONE_PULSE2, TONE_TRIANGLE};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, variant_count::VariantCount)]
+pub enum Sfx {
+    Jump,
+    /// Hitting a high-elasticity segment, e.g. `JumpyTile`
+    Bounce,
+    Land,
+    ItemTouched,
+    MapOpen,
+    Hurt,
+}
+
+pub struct SfxParams {
+    pub channel: u32,
+    /// Mode (duty cycle) bits of `tone` flags
+    pub mode: u32,
+    pub freq_start: u16,
+    pub freq_end: u16,
+    pub volume: u8,
+    /// Frames
+    pub duration: u8,
+    /// Effect with higher priority is not interrupted by lower priority effects on the same channel
+    pub priority: u8,
+}
+
+impl Sfx {
+    pub const fn params(self) -> SfxParams {
+        match self {
+            Sfx::Jump => SfxParams { channel: TONE_PULSE1, mode: TONE_MODE2, freq_start: 300, freq_end: 600, volume: 30, duration: 8, priority: 1 },
+            Sfx::Bounce => SfxParams { channel: TONE_PULSE1, mode: TONE_MODE3, freq_start: 200, freq_end: 900, volume: 40, duration: 12, priority: 2 },
+            Sfx::Land => SfxParams { channel: TONE_NOISE, mode: 0, freq_start: 140, freq_end: 60, volume: 25, duration: 4, priority: 1 },
+            Sfx::ItemTouched => SfxParams { channel: TONE_PULSE2, mode: TONE_MODE2, freq_start: 880, freq_end: 1320, volume: 50, duration: 20, priority: 3 },
+            Sfx::MapOpen => SfxParams { channel: TONE_TRIANGLE, mode: 0, freq_start: 440, freq_end: 220, volume: 50, duration: 10, priority: 1 },
+            Sfx::Hurt => SfxParams { channel: TONE_NOISE, mode: 0, freq_start: 400, freq_end: 80, volume: 60, duration: 16, priority: 3 },
+        }
+    }
+
+    pub fn play_now(self) {
+        let p = self.params();
+        tone(p.freq_start as u32 | (p.freq_end as u32) << 16, p.volume as u32, p.duration as u32, p.channel | p.mode);
+    }
+}
+
+/// Effects requested during a frame, as a bitfield
+#[derive(Clone, Copy, Default)]
+pub struct SfxRequests(u8);
+
+impl SfxRequests {
+    pub const fn new() -> SfxRequests {
+        SfxRequests(0)
+    }
+
+    pub fn request(&mut self, sfx: Sfx) {
+        self.0 |= 1 << sfx as u8;
+    }
+
+    pub fn contains(&self, sfx: Sfx) -> bool {
+        self.0 & (1 << sfx as u8) != 0
+    }
+
+    pub fn extend(&mut self, other: SfxRequests) {
+        self.0 |= other.0;
+    }
+
+    /// Return the requests, leaving none
+    pub fn take(&mut self) -> SfxRequests {
+        std::mem::take(self)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Playing {
+    priority: u8,
+    frames_left: u8,
+}
+
+pub struct Sounds {
+    pub requests: SfxRequests,
+    channels: [Playing; 4],
+    /// Plays on channels not taken by sound effects
+    pub music: MusicPlayer,
+}
+
+impl Sounds {
+    pub const fn new() -> Sounds {
+        Sounds {
+            requests: SfxRequests::new(),
+            channels: [Playing { priority: 0, frames_left: 0 }; 4],
+            music: MusicPlayer::new(),
+        }
+    }
+
+    pub fn request(&mut self, sfx: Sfx) {
+        self.requests.request(sfx);
+    }
+
+    /// Start requested effects and advance the music. Call exactly once per frame.
+    pub fn update(&mut self) {
+        let requests = self.requests.take();
+
+        let mut chosen: [Option<Sfx>; 4] = [None; 4];
+        for sfx in ALL_SFX {
+            if !requests.contains(sfx) {
+                continue;
+            }
+            let p = sfx.params();
+            let slot = &mut chosen[p.channel as usize];
+            match slot {
+                Some(other) if other.params().priority >= p.priority => (),
+                _ => *slot = Some(sfx),
+            }
+        }
+
+        let mut busy = [false; 4];
+        for ((channel, sfx), busy) in self.channels.iter_mut().zip(chosen).zip(&mut busy) {
+            if let Some(sfx) = sfx {
+                let p = sfx.params();
+                if channel.frames_left == 0 || p.priority > channel.priority {
+                    sfx.play_now();
+                    *channel = Playing { priority: p.priority, frames_left: p.duration };
+                }
+            }
+            *busy = channel.frames_left > 0;
+            channel.frames_left = channel.frames_left.saturating_sub(1);
+        }
+        self.music.update(busy);
+    }
+}
+
+const ALL_SFX: [Sfx; Sfx::VARIANT_COUNT] = [Sfx::Jump, Sfx::Bounce, Sfx::Land, Sfx::ItemTouched, Sfx::MapOpen, Sfx::Hurt];
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::wasm4::{self, take_tones};
+
+    fn played_channels() -> Vec<u32> {
+        take_tones().iter().map(|t| t[3] & 0b11).collect()
+    }
+
+    #[test]
+    fn higher_priority_wins_within_frame() {
+        let _guard = wasm4::exclusive();
+        let mut sounds = Sounds::new();
+        sounds.request(Sfx::Jump);
+        sounds.request(Sfx::Bounce);
+        sounds.request(Sfx::Land);
+        sounds.update();
+        let tones = take_tones();
+        assert_eq!(tones.len(), 2);
+        assert!(tones.iter().any(|t| t[0] == 200 | 900 << 16));
+        assert!(tones.iter().any(|t| t[3] == TONE_NOISE));
+    }
+
+    #[test]
+    fn lower_priority_waits_until_effect_ends() {
+        let _guard = wasm4::exclusive();
+        let mut sounds = Sounds::new();
+        sounds.request(Sfx::Bounce);
+        sounds.update();
+        assert_eq!(played_channels(), [TONE_PULSE1]);
+
+        sounds.request(Sfx::Jump);
+        sounds.update();
+        assert!(played_channels().is_empty());
+
+        for _ in 0..Sfx::Bounce.params().duration {
+            sounds.update();
+        }
+        sounds.request(Sfx::Jump);
+        sounds.update();
+        assert_eq!(played_channels(), [TONE_PULSE1]);
+    }
+
+    #[test]
+    fn higher_priority_interrupts() {
+        let _guard = wasm4::exclusive();
+        let mut sounds = Sounds::new();
+        sounds.request(Sfx::Jump);
+        sounds.update();
+        sounds.request(Sfx::Bounce);
+        sounds.update();
+        assert_eq!(played_channels(), [TONE_PULSE1, TONE_PULSE1]);
+    }
+}
@@ -1,4 +1,28 @@
-use crate::utils::{sprite8x8,sprite16x16};
+use crate::utils::{sprite8x8,sprite16x16,sprite1bpp,sprite2bpp};
+use crate::wasm4::{BLIT_1BPP, BLIT_2BPP, blit_sub};
+
+/// Frames of equal size side by side in one sprite
+#[derive(Clone, Copy)]
+pub struct SpriteSheet {
+    pub data: &'static [u8],
+    /// Of the whole sheet, in pixels
+    pub width: u32,
+    pub frame: (u32, u32),
+    /// `BLIT_1BPP` or `BLIT_2BPP`
+    pub flags: u32,
+}
+
+impl SpriteSheet {
+    /// Draw frame `index` with its upper left corner at `x`, `y`. `flags` are added to those of the sheet.
+    pub fn blit(&self, index: u32, x: i32, y: i32, flags: u32) {
+        blit_sub(self.data, x, y, self.frame.0, self.frame.1, index * self.frame.0, 0, self.width, self.flags | flags);
+    }
+
+    pub const fn len(&self) -> u32 {
+        self.width / self.frame.0
+    }
+}
+
 pub static WHEEL_S: [u8; 8] = sprite8x8(
     b"
     |. X . . X . . .|
@@ -12,47 +36,29 @@ pub static WHEEL_S: [u8; 8] = sprite8x8(
 ",
 );
 
-pub static WHEEL1: [u8; 32] = sprite16x16(
+/// Two frames of the rolling wheel, with the hub in colour 1
+pub static WHEEL: [u8; 128] = sprite2bpp(32, 16,
     b"
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . X . . . X . . . . . . .|  
-    |. . . . . X . . X . . . X . . .|
-    |. . . . . . X X X X . X . . . .|
-    |. . . . . X . . . . X . . . . .|
-    |. . . X X X . . . . X . . . . .|
-    |. . . . . X . . . . X X X . . .|
-    |. . . . . X . . . . X . . . . .|
-    |. . . . X . X X X X . . . . . .|
-    |. . . . . . X . . . X . . . . .|
-    |. . . . . X . . . . . X . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
+    |. . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . .|
+    |. . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . .|
+    |. . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . .|
+    |. . . . X . . . X . . . . . . . . . . . . . X . . . X . . . . .|
+    |. . . . . X . . X . . . X . . . . . . . . . X . . X . . . . . .|
+    |. . . . . . X X X X . X . . . . . . . X . . . X X X . . . . . .|
+    |. . . . . X - - - - X . . . . . . . . . X . X - - - X X . . . .|
+    |. . . X X X - - - - X . . . . . . . . . . X - - - - - X X X . .|
+    |. . . . . X - - - - X X X . . . . . . X X X - - - - - X . . . .|
+    |. . . . . X - - - - X . . . . . . . . . . X - - - - X . . . . .|
+    |. . . . X . X X X X . . . . . . . . . . X . X X X X . X . . . .|
+    |. . . . . . X . . . X . . . . . . . . X . . . X . . . . X . . .|
+    |. . . . . X . . . . . X . . . . . . . . . . . X . . . . . . . .|
+    |. . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . .|
+    |. . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . .|
+    |. . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . .|
 ",
 );
 
-pub static WHEEL2: [u8; 32] = sprite16x16(
-    b"
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . X . . . X . . . . .|  
-    |. . . . . . X . . X . . . . . .|
-    |. . . X . . . X X X . . . . . .|
-    |. . . . X . X . . . X X . . . .|
-    |. . . . . X . . . . . X X X . .|
-    |. . . X X X . . . . . X . . . .|
-    |. . . . . X . . . . X . . . . .|
-    |. . . . X . X X X X . X . . . .|
-    |. . . X . . . X . . . . X . . .|
-    |. . . . . . . X . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-    |. . . . . . . . . . . . . . . .|
-",
-);
+pub const WHEEL_SHEET: SpriteSheet = SpriteSheet { data: &WHEEL, width: 32, frame: (16, 16), flags: BLIT_2BPP };
 
 pub static SOLIDTILE: [u8; 8] = sprite8x8(
     b"
@@ -97,6 +103,301 @@ pub static LADDER1: [u8; 8] = sprite8x8(
 
 
 
+pub static PLATFORM: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. X X X X X X .|
+    |. X . X X . X .|
+    |. X X . . X X .|
+    |. X X . . X X .|
+    |. X . X X . X .|
+    |. X X X X X X .|
+    |. . . . . . . .|
+",
+);
+
+/// Squashing and springing back after a bounce
+pub static JUMPY_PULSE: [u8; 24] = sprite1bpp(24, 8,
+    b"
+    |. . . . . . . . . . . . . . . . . . X X . . X X|
+    |. . . . . . . . . . X X . . X X X X . . X X . .|
+    |X X X X . X X X X X . . X X . . . X . X . X . X|
+    |X X . . X X . . . X . X . X . X X . X . X . X .|
+    |. X . X . X . X X . X . X . X . . X . X . X . X|
+    |X . X . X . X . X . X . X . X . X . X . X . X .|
+    |. X . X . X . X . X . X . X . X . X . X . X . X|
+    |X . X . X . X . X . X . X . X . X . X . X . X .|
+",
+);
+
+pub const JUMPY_SHEET: SpriteSheet = SpriteSheet { data: &JUMPY_PULSE, width: 24, frame: (8, 8), flags: BLIT_1BPP };
+
+/// Flickering flame
+pub static TORCH: [u8; 24] = sprite1bpp(24, 8,
+    b"
+    |. . . X . . . . . . . . X . . . . . . X . . . .|
+    |. . . X X . . . . . . X X . . . . . X X X . . .|
+    |. . X X X . . . . . X X X X . . . . X X X . . .|
+    |. . X X X X . . . . X X X . . . . X X X X . . .|
+    |. . . X X . . . . . . X X . . . . . X X . . . .|
+    |. . X X X X . . . . X X X X . . . X X X X . . .|
+    |. . . X X . . . . . . X X . . . . . X X . . . .|
+    |. . . X X . . . . . . X X . . . . . X X . . . .|
+",
+);
+
+pub const TORCH_SHEET: SpriteSheet = SpriteSheet { data: &TORCH, width: 24, frame: (8, 8), flags: BLIT_1BPP };
+
+pub static SPIKES: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. X . . . X . .|
+    |. X . . . X . .|
+    |X . X . X . X .|
+    |X . X . X . X X|
+    |X X X X X X X X|
+",
+);
+
+pub static ONE_WAY: [u8; 8] = sprite8x8(
+    b"
+    |X X X X X X X X|
+    |X . . X X . . X|
+    |. X . . . . X .|
+    |. . X . . X . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+",
+);
+
+pub static WATER: [u8; 16] = sprite2bpp(8, 8,
+    b"
+    |. o o . . . . .|
+    |- . . - . . . o|
+    |. . . . - - - .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . o o . .|
+    |. . . - . . - .|
+    |. . . . . . . .|
+",
+);
+
+/// Waves moving right
+pub static WATER_WAVES: [u8; 32] = sprite2bpp(16, 8,
+    b"
+    |. o o . . . . . . . o o . . . .|
+    |- . . - . . . o . o . . - . . .|
+    |. . . . - - - . - . . . . - - .|
+    |. . . . . . . . . . . . . . . .|
+    |. . . . . . . . . . . . . . . .|
+    |. . . . o o . . . . . . . o o .|
+    |. . . - . . - . . . . . - . . -|
+    |. . . . . . . . . . . . . . . .|
+",
+);
+
+pub const WATER_SHEET: SpriteSheet = SpriteSheet { data: &WATER_WAVES, width: 16, frame: (8, 8), flags: BLIT_2BPP };
+
+pub static LOW_GRAVITY: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . X .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . X . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+",
+);
+
+pub static CONVEYOR_LEFT: [u8; 8] = sprite8x8(
+    b"
+    |X X X X X X X X|
+    |. . X . . . X .|
+    |. X X . . X X .|
+    |X X X . X X X .|
+    |. X X . . X X .|
+    |. . X . . . X .|
+    |X X X X X X X X|
+    |X . X . X . X .|
+",
+);
+
+pub static CONVEYOR_RIGHT: [u8; 8] = sprite8x8(
+    b"
+    |X X X X X X X X|
+    |. X . . . X . .|
+    |. X X . . X X .|
+    |. X X X . X X X|
+    |. X X . . X X .|
+    |. X . . . X . .|
+    |X X X X X X X X|
+    |X . X . X . X .|
+",
+);
+
+pub static SLOPE45_UP: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . X|
+    |. . . . . . X .|
+    |. . . . . X . X|
+    |. . . . X . X .|
+    |. . . X . X . X|
+    |. . X . X . X .|
+",
+);
+
+pub static SLOPE45_DOWN: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |X . . . . . . .|
+    |. X . . . . . .|
+    |X . X . . . . .|
+    |. X . X . . . .|
+    |X . X . X . . .|
+    |. X . X . X . .|
+",
+);
+
+pub static SLOPE22_UP_LOWER: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . X|
+    |. . . . . . X .|
+    |. . . X . X . X|
+    |. . X . X . X .|
+",
+);
+
+pub static SLOPE22_UP_UPPER: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . X|
+    |. . . . . . X .|
+    |. . . X . X . X|
+    |. . X . X . X .|
+    |. X . X . X . X|
+    |X . X . X . X .|
+    |. X . X . X . X|
+    |X . X . X . X .|
+",
+);
+
+pub static SLOPE22_DOWN_UPPER: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |X . X . . . . .|
+    |. X . X . . . .|
+    |X . X . X . X .|
+    |. X . X . X . X|
+    |X . X . X . X .|
+    |. X . X . X . X|
+    |X . X . X . X .|
+",
+);
+
+pub static SLOPE22_DOWN_LOWER: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |X . X . . . . .|
+    |. X . X . . . .|
+    |X . X . X . X .|
+",
+);
+
+pub static ROUND_CORNER_L: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . X . X|
+    |. . . . X . X .|
+    |. . . X . X . X|
+    |. . X . X . X .|
+    |. X . X . X . X|
+    |X . X . X . X .|
+    |. X . X . X . X|
+    |X . X . X . X .|
+",
+);
+
+pub static ROUND_CORNER_R: [u8; 8] = sprite8x8(
+    b"
+    |. X . . . . . .|
+    |X . X . X . . .|
+    |. X . X . X . .|
+    |X . X . X . X .|
+    |. X . X . X . .|
+    |X . X . X . X .|
+    |. X . X . X . X|
+    |X . X . X . X .|
+",
+);
+
+pub static PIPE_L: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |X . . . . . . .|
+    |. . . . . . . .|
+    |X . . . . . . .|
+    |. X . . . . . .|
+    |X . X . X . . .|
+",
+);
+
+pub static PIPE_R: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . .|
+    |. . . . . . . X|
+    |. . . . . . X .|
+    |. . . . . X . X|
+    |. . . . X . X .|
+",
+);
+
+pub static WALKER: [u8; 8] = sprite8x8(
+    b"
+    |. . X X X X . .|
+    |. X . . . . X .|
+    |X . X . . X . X|
+    |X . . . . . . X|
+    |X . X X X X . X|
+    |. X . . . . X .|
+    |. X X . . X X .|
+    |X . . . . . . X|
+",
+);
+
+pub static DROPPER: [u8; 8] = sprite8x8(
+    b"
+    |X . X . . X . X|
+    |. X X X X X X .|
+    |X . . . . . . X|
+    |X . X . . X . X|
+    |X . . . . . . X|
+    |. X . X X . X .|
+    |. . X . . X . .|
+    |. . . X X . . .|
+",
+);
+
 pub static INFOBOX1: [u8; 8] = sprite8x8(
     b"
     |. . .   X . . .|
@@ -126,6 +427,19 @@ pub static INFOBOX2: [u8; 8] = sprite8x8(
 
 
 
+pub static CHECKPOINT: [u8; 8] = sprite8x8(
+    b"
+    |. X X X X . . .|
+    |. X X X X X X .|
+    |. X X X X X X .|
+    |. X . . X X X .|
+    |. X . . . . . .|
+    |. X . . . . . .|
+    |. X . . . . . .|
+    |X X X . . . . .|
+",
+);
+
 pub static STAR1: [u8; 8] = sprite8x8(
     b"
     |. . . X   . . .|
@@ -165,3 +479,58 @@ pub static CRATE: [u8; 8] = sprite8x8(
     |X X X X X X .  |
 ",
 );
+
+pub static BG_PEBBLES: [u8; 8] = sprite8x8(
+    b"
+    |. . . . . . . .|
+    |. . X . . . . .|
+    |. X X X . . . .|
+    |. . . . . . . .|
+    |. . . . . X X .|
+    |. . . . X X X X|
+    |. . . . . . . .|
+    |. . . . . . . .|
+",
+);
+
+pub static BG_ARCH: [u8; 32] = sprite16x16(
+    b"
+    |. . . . . X X X X X X . . . . .|
+    |. . . X X . . . . . . X X . . .|
+    |. . X . . . . . . . . . . X . .|
+    |. X . . . . . . . . . . . . X .|
+    |. X . . . . . . . . . . . . X .|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X . . . . . . . . . . . . . . X|
+    |X X X X X X X X X X X X X X X X|
+",
+);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{utils::draw_colours, wasm4::{self, get_pixel}};
+
+    #[test]
+    fn sheet_frames() {
+        let _guard = wasm4::exclusive();
+        static DATA: [u8; 2] = sprite2bpp(4, 2, b"
+            |- o X X|
+            |. . o -|
+        ");
+        let sheet = SpriteSheet { data: &DATA, width: 4, frame: (2, 2), flags: BLIT_2BPP };
+        draw_colours(0, 2, 3, 4);
+        sheet.blit(0, 0, 0, 0);
+        sheet.blit(1, 10, 0, 0);
+        assert_eq!([get_pixel(0, 0), get_pixel(1, 0), get_pixel(0, 1), get_pixel(1, 1)], [1, 2, 0, 0]);
+        assert_eq!([get_pixel(10, 0), get_pixel(11, 0), get_pixel(10, 1), get_pixel(11, 1)], [3, 3, 2, 1]);
+    }
+}
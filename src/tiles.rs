@@ -1,4 +1,4 @@
-use crate::{sprites, cf32};
+use crate::{Real, real, sprites::{self, SpriteSheet}, cf32};
 
 pub struct CollisionSegment {
     /// Relative position against center of the tile, start of the segment
@@ -11,12 +11,147 @@ pub struct CollisionSegment {
     pub el: f32,
 }
 
+impl CollisionSegment {
+    /// Find the segment with the nearest surface to `point`.
+    /// Returns it together with the vector from the closest point of its centerline to `point`.
+    pub fn closest(config: &[CollisionSegment], center: cf32, point: cf32) -> Option<(&CollisionSegment, cf32)> {
+        let mut attained_distance = Real::MAX;
+        let mut chosen = None;
+
+        for colsegm in config {
+            let pos1 = center + colsegm.rp1;
+            let pos2 = center + colsegm.rp2;
+
+            let projected_forcepoint = {
+                let a = point - pos1;
+                let b = pos2 - pos1;
+                let x = (a / b).re.clamp(0.0, 1.0);
+                pos1 * (1.0 - x) + pos2 * x
+            };
+
+            let v = point - projected_forcepoint;
+            let vn = v.norm();
+
+            if attained_distance > vn + colsegm.rad {
+                attained_distance = vn + colsegm.rad;
+                chosen = Some((colsegm, v));
+            }
+        }
+        chosen
+    }
+
+    /// Move a circle of `radius` from `from` along the unit vector `dir` by up to `length` and find the first segment
+    /// it runs into. Returns the segment, the distance travelled until touching it and the surface normal there.
+    /// Segments the circle already overlaps at `from` are ignored.
+    pub fn sweep(config: &[CollisionSegment], center: cf32, from: cf32, dir: cf32, length: Real, radius: Real) -> Option<(&CollisionSegment, Real, cf32)> {
+        let mut attained_distance = length;
+        let mut chosen = None;
+
+        for colsegm in config {
+            let r = radius + colsegm.rad;
+            let pos1 = center + colsegm.rp1;
+            let pos2 = center + colsegm.rp2;
+            let mut hit = |s: Real, normal: cf32| {
+                if s < attained_distance {
+                    attained_distance = s;
+                    chosen = Some((colsegm, s, normal));
+                }
+            };
+
+            // the straight sides
+            let seglen = (pos2 - pos1).norm();
+            if seglen > 0.0 {
+                let along = (pos2 - pos1).unscale(seglen);
+                let mut normal = along * cf32::new(0.0, 1.0);
+                let mut dist = ((from - pos1) / normal).re;
+                if dist < 0.0 {
+                    normal = -normal;
+                    dist = -dist;
+                }
+                let approach = -(dir / normal).re;
+                if dist >= r && approach > 0.0 {
+                    let s = (dist - r) / approach;
+                    let x = ((from + dir.scale(s) - pos1) / along).re;
+                    if x >= 0.0 && x <= seglen {
+                        hit(s, normal);
+                    }
+                }
+            }
+
+            // the rounded ends
+            for end in [pos1, pos2] {
+                let w = (end - from) / dir;
+                if w.re > 0.0 && w.im.abs() < r && (end - from).norm() > r {
+                    let s = w.re - (r * r - w.im * w.im).sqrt();
+                    hit(s, (from + dir.scale(s) - end).unscale(r));
+                }
+            }
+        }
+        chosen
+    }
+
+    /// Whether a circle of `radius` around `point` overlaps any of the segments
+    pub fn touches(config: &[CollisionSegment], center: cf32, point: cf32, radius: Real) -> bool {
+        match CollisionSegment::closest(config, center, point) {
+            Some((segm, v)) => v.norm() < radius + segm.rad,
+            None => false,
+        }
+    }
+}
+
+
+/// How a tile changes the movement of the player
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Physics {
+    /// Multiplier of gravity. Negative values make the player float up.
+    pub gravity: Real,
+    /// Part of velocity lost per movement unit
+    pub drag: Real,
+    /// Constant acceleration, e.g. of a conveyor belt
+    pub push: cf32,
+    /// The d-pad moves the player in the air as fast as on the ground
+    pub swim: bool,
+}
+
+impl Physics {
+    pub const NORMAL: Physics = Physics { gravity: real(1.0), drag: real(1.0 / 2000.0), push: cf32::new(0.0, 0.0), swim: false };
+    const WATER: Physics = Physics { gravity: real(-0.2), drag: real(1.0 / 200.0), push: cf32::new(0.0, 0.0), swim: true };
+    const LOW_GRAVITY: Physics = Physics { gravity: real(0.3), ..Physics::NORMAL };
+    const CONVEYOR_LEFT: Physics = Physics { push: cf32::new(-0.3, 0.0), ..Physics::NORMAL };
+    const CONVEYOR_RIGHT: Physics = Physics { push: cf32::new(0.3, 0.0), ..Physics::NORMAL };
+}
+
+/// Sprites a tile cycles through
+pub struct Animation {
+    /// 8x8 frames
+    pub frames: SpriteSheet,
+    /// Game frames each of them is shown
+    pub frame_time: u8,
+    /// Played once after the player bounces off the tile instead of looping, `sprite` is shown otherwise
+    pub on_bounce: bool,
+}
+
+impl Animation {
+    const JUMPY: Animation = Animation { frames: sprites::JUMPY_SHEET, frame_time: 4, on_bounce: true };
+    const WATER: Animation = Animation { frames: sprites::WATER_SHEET, frame_time: 30, on_bounce: false };
+    const TORCH: Animation = Animation { frames: sprites::TORCH_SHEET, frame_time: 6, on_bounce: false };
+}
+
 
 #[enum_dispatch::enum_dispatch(TileTypeEnum)]
 pub trait TileType {
     fn collision_configuration(self) -> &'static [CollisionSegment];
     fn sprite(self) -> Option<&'static [u8; 8]>;
+    /// Drawn instead of `sprite` with colours 1, 2 and 3 of the sprite being the tile colour, white and blue
+    fn sprite_2bpp(self) -> Option<&'static [u8; 16]> where Self: Sized { None }
     fn map_viewer_colour(self) -> u8;
+    /// Touching the tile damages the player
+    fn hurts(self) -> bool where Self: Sized { false }
+    /// Collides only with the player coming from above, who can also drop through it
+    fn one_way(self) -> bool where Self: Sized { false }
+    /// Applies while the player is inside the tile, or stands on it if the tile is solid
+    fn physics(self) -> Physics where Self: Sized { Physics::NORMAL }
+    fn animation(self) -> Option<&'static Animation> where Self: Sized { None }
 }
 
 
@@ -75,6 +210,9 @@ impl TileType for JumpyTile {
     fn map_viewer_colour(self) -> u8 {
         1
     }
+    fn animation(self) -> Option<&'static Animation> {
+        Some(&Animation::JUMPY)
+    }
 }
 
 
@@ -99,6 +237,358 @@ impl TileType for Ladder1Tile {
 }
 
 
+#[derive(Clone, Copy)]
+pub struct SpikesTile;
+/// Low block, so that spikes can be crossed at the cost of health
+static SPIKES_TILE : [CollisionSegment; 4] = [
+    CollisionSegment{rp1: cf32::new(-3.0,   1.0), rp2: cf32::new(3.0,   1.0), rad:1.0, el: 0.01},
+    CollisionSegment{rp1: cf32::new( 3.0,   1.0), rp2: cf32::new(3.0,   3.0), rad:1.0, el: 0.01},
+    CollisionSegment{rp1: cf32::new( 3.0,   3.0), rp2: cf32::new(-3.0,  3.0), rad:1.0, el: 0.01},
+    CollisionSegment{rp1: cf32::new(-3.0,   3.0), rp2: cf32::new(-3.0,  1.0), rad:1.0, el: 0.01},
+];
+impl TileType for SpikesTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SPIKES_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::SPIKES)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+    fn hurts(self) -> bool {
+        true
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct OneWayTile;
+static ONE_WAY_TILE : [CollisionSegment; 1] = [
+    CollisionSegment{rp1: cf32::new(-4.0,  -3.0), rp2: cf32::new(4.0,  -3.0), rad:1.0, el: 0.01},
+];
+impl TileType for OneWayTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &ONE_WAY_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::ONE_WAY)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+    fn one_way(self) -> bool {
+        true
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct WaterTile;
+impl TileType for WaterTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &EMRTY_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        None
+    }
+    fn sprite_2bpp(self) -> Option<&'static [u8; 16]> {
+        Some(&sprites::WATER)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        0
+    }
+    fn physics(self) -> Physics {
+        Physics::WATER
+    }
+    fn animation(self) -> Option<&'static Animation> {
+        Some(&Animation::WATER)
+    }
+}
+
+
+/// Decoration
+#[derive(Clone, Copy)]
+pub struct TorchTile;
+impl TileType for TorchTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &EMRTY_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        None
+    }
+    fn map_viewer_colour(self) -> u8 {
+        0
+    }
+    fn animation(self) -> Option<&'static Animation> {
+        Some(&Animation::TORCH)
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct LowGravityTile;
+impl TileType for LowGravityTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &EMRTY_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::LOW_GRAVITY)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        0
+    }
+    fn physics(self) -> Physics {
+        Physics::LOW_GRAVITY
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct ConveyorLeftTile;
+impl TileType for ConveyorLeftTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SQUARE_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::CONVEYOR_LEFT)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+    fn physics(self) -> Physics {
+        Physics::CONVEYOR_LEFT
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct ConveyorRightTile;
+impl TileType for ConveyorRightTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SQUARE_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::CONVEYOR_RIGHT)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+    fn physics(self) -> Physics {
+        Physics::CONVEYOR_RIGHT
+    }
+}
+
+
+// Slopes and curves only collide at their walkable surface and are meant to lie on solid ground.
+// Surfaces are below the drawn edge by `rad` (measured across the slope), so that they line up with `SQUARE_TILE` neighbours.
+
+const ARC_SEGMENTS: usize = 6;
+/// Rotation by 90° / `ARC_SEGMENTS`: cos and sin of 15°
+const ARC_STEP: (f32, f32) = (0.965_925_8, 0.258_819_04);
+
+/// Segments along a quarter of a circle around `center`, starting at `center + from`.
+/// Turns clockwise on screen if `clockwise`, otherwise counterclockwise.
+/// Computed in `f32`, as fixed-point arithmetic is not available in `const fn`.
+const fn quarter_arc(center: (f32, f32), from: (f32, f32), clockwise: bool) -> [CollisionSegment; ARC_SEGMENTS] {
+    const UNSET: CollisionSegment = CollisionSegment{rp1: cf32::new(0.0, 0.0), rp2: cf32::new(0.0, 0.0), rad:1.0, el: 0.01};
+    let step = if clockwise { ARC_STEP } else { (ARC_STEP.0, -ARC_STEP.1) };
+    let mut segments = [UNSET; ARC_SEGMENTS];
+    let mut v = from;
+    let mut i = 0;
+    while i < ARC_SEGMENTS {
+        let next = (v.0 * step.0 - v.1 * step.1, v.0 * step.1 + v.1 * step.0);
+        segments[i].rp1 = cf32::new(center.0 + v.0, center.1 + v.1);
+        segments[i].rp2 = cf32::new(center.0 + next.0, center.1 + next.1);
+        v = next;
+        i += 1;
+    }
+    segments
+}
+
+
+/// 45° slope rising to the right
+#[derive(Clone, Copy)]
+pub struct Slope45UpTile;
+static SLOPE45_UP_TILE : [CollisionSegment; 1] = [
+    CollisionSegment{rp1: cf32::new(-4.0, 5.414), rp2: cf32::new( 4.0, -2.586), rad:1.0, el: 0.01},
+];
+impl TileType for Slope45UpTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SLOPE45_UP_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::SLOPE45_UP)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// 45° slope falling to the right
+#[derive(Clone, Copy)]
+pub struct Slope45DownTile;
+static SLOPE45_DOWN_TILE : [CollisionSegment; 1] = [
+    CollisionSegment{rp1: cf32::new(-4.0, -2.586), rp2: cf32::new( 4.0, 5.414), rad:1.0, el: 0.01},
+];
+impl TileType for Slope45DownTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SLOPE45_DOWN_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::SLOPE45_DOWN)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Lower half of a 22.5° slope rising to the right, followed by `Slope22UpUpperTile`
+#[derive(Clone, Copy)]
+pub struct Slope22UpLowerTile;
+static SLOPE22_UP_LOWER_TILE : [CollisionSegment; 1] = [
+    CollisionSegment{rp1: cf32::new(-4.0, 5.118), rp2: cf32::new( 4.0, 1.118), rad:1.0, el: 0.01},
+];
+impl TileType for Slope22UpLowerTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SLOPE22_UP_LOWER_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::SLOPE22_UP_LOWER)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Upper half of a 22.5° slope rising to the right
+#[derive(Clone, Copy)]
+pub struct Slope22UpUpperTile;
+static SLOPE22_UP_UPPER_TILE : [CollisionSegment; 1] = [
+    CollisionSegment{rp1: cf32::new(-4.0, 1.118), rp2: cf32::new( 4.0, -2.882), rad:1.0, el: 0.01},
+];
+impl TileType for Slope22UpUpperTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SLOPE22_UP_UPPER_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::SLOPE22_UP_UPPER)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Upper half of a 22.5° slope falling to the right, followed by `Slope22DownLowerTile`
+#[derive(Clone, Copy)]
+pub struct Slope22DownUpperTile;
+static SLOPE22_DOWN_UPPER_TILE : [CollisionSegment; 1] = [
+    CollisionSegment{rp1: cf32::new(-4.0, -2.882), rp2: cf32::new( 4.0, 1.118), rad:1.0, el: 0.01},
+];
+impl TileType for Slope22DownUpperTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SLOPE22_DOWN_UPPER_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::SLOPE22_DOWN_UPPER)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Lower half of a 22.5° slope falling to the right
+#[derive(Clone, Copy)]
+pub struct Slope22DownLowerTile;
+static SLOPE22_DOWN_LOWER_TILE : [CollisionSegment; 1] = [
+    CollisionSegment{rp1: cf32::new(-4.0, 1.118), rp2: cf32::new( 4.0, 5.118), rad:1.0, el: 0.01},
+];
+impl TileType for Slope22DownLowerTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &SLOPE22_DOWN_LOWER_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::SLOPE22_DOWN_LOWER)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Rounded upper left corner of a block
+#[derive(Clone, Copy)]
+pub struct RoundCornerLTile;
+static ROUND_CORNER_L_TILE : [CollisionSegment; ARC_SEGMENTS] = quarter_arc(( 4.0,  4.0), (0.0, -7.0), false);
+impl TileType for RoundCornerLTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &ROUND_CORNER_L_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::ROUND_CORNER_L)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Rounded upper right corner of a block
+#[derive(Clone, Copy)]
+pub struct RoundCornerRTile;
+static ROUND_CORNER_R_TILE : [CollisionSegment; ARC_SEGMENTS] = quarter_arc((-4.0,  4.0), (0.0, -7.0), true);
+impl TileType for RoundCornerRTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &ROUND_CORNER_R_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::ROUND_CORNER_R)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Floor curving up into a wall on the left, like the inside of a pipe
+#[derive(Clone, Copy)]
+pub struct PipeLTile;
+static PIPE_L_TILE : [CollisionSegment; ARC_SEGMENTS] = quarter_arc(( 4.0, -4.0), (0.0,  9.0), true);
+impl TileType for PipeLTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &PIPE_L_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::PIPE_L)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
+/// Floor curving up into a wall on the right, like the inside of a pipe
+#[derive(Clone, Copy)]
+pub struct PipeRTile;
+static PIPE_R_TILE : [CollisionSegment; ARC_SEGMENTS] = quarter_arc((-4.0, -4.0), (0.0,  9.0), false);
+impl TileType for PipeRTile {
+    fn collision_configuration(self) -> &'static [CollisionSegment] {
+        &PIPE_R_TILE
+    }
+    fn sprite(self) -> Option<&'static [u8; 8]> {
+        Some(&sprites::PIPE_R)
+    }
+    fn map_viewer_colour(self) -> u8 {
+        1
+    }
+}
+
+
 #[enum_dispatch::enum_dispatch]
 #[derive(Clone, Copy)]
 pub enum TileTypeEnum {
@@ -106,6 +596,23 @@ pub enum TileTypeEnum {
     UsualArea1Tile,
     JumpyTile,
     Ladder1Tile,
+    SpikesTile,
+    OneWayTile,
+    WaterTile,
+    TorchTile,
+    LowGravityTile,
+    ConveyorLeftTile,
+    ConveyorRightTile,
+    Slope45UpTile,
+    Slope45DownTile,
+    Slope22UpLowerTile,
+    Slope22UpUpperTile,
+    Slope22DownUpperTile,
+    Slope22DownLowerTile,
+    RoundCornerLTile,
+    RoundCornerRTile,
+    PipeLTile,
+    PipeRTile,
 }
 
 /// TODO: make this automatic somehow
@@ -116,6 +623,261 @@ pub const fn tile_type_enum_eq(a: TileTypeEnum, b: TileTypeEnum) -> bool {
         (UsualArea1Tile(..), UsualArea1Tile(..)) => true,
         (JumpyTile(..), JumpyTile(..)) => true,
         (Ladder1Tile(..), Ladder1Tile(..)) => true,
+        (SpikesTile(..), SpikesTile(..)) => true,
+        (OneWayTile(..), OneWayTile(..)) => true,
+        (WaterTile(..), WaterTile(..)) => true,
+        (TorchTile(..), TorchTile(..)) => true,
+        (LowGravityTile(..), LowGravityTile(..)) => true,
+        (ConveyorLeftTile(..), ConveyorLeftTile(..)) => true,
+        (ConveyorRightTile(..), ConveyorRightTile(..)) => true,
+        (Slope45UpTile(..), Slope45UpTile(..)) => true,
+        (Slope45DownTile(..), Slope45DownTile(..)) => true,
+        (Slope22UpLowerTile(..), Slope22UpLowerTile(..)) => true,
+        (Slope22UpUpperTile(..), Slope22UpUpperTile(..)) => true,
+        (Slope22DownUpperTile(..), Slope22DownUpperTile(..)) => true,
+        (Slope22DownLowerTile(..), Slope22DownLowerTile(..)) => true,
+        (RoundCornerLTile(..), RoundCornerLTile(..)) => true,
+        (RoundCornerRTile(..), RoundCornerRTile(..)) => true,
+        (PipeLTile(..), PipeLTile(..)) => true,
+        (PipeRTile(..), PipeRTile(..)) => true,
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Scalar, TilePos, UniqueItem, platforms::Platforms, player::Player, wasm4::{BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT}, world::World};
+
+    /// Highest point of the surface at `x`, relative to the tile center
+    fn surface_height(tile: TileTypeEnum, x: f32) -> f32 {
+        let mut y = -8.0;
+        while y < 8.0 && !CollisionSegment::touches(tile.collision_configuration(), cf32::new(0.0, 0.0), cf32::new(x, y), real(0.01)) {
+            y += 0.01;
+        }
+        y
+    }
+
+    #[test]
+    fn surfaces_line_up_with_neighbours() {
+        // Tops of square tiles on the sides and of the floor below are at -4 and 4, rounded ends of segments
+        // leave less than half a pixel at the joints
+        let cases: [(TileTypeEnum, f32, f32); 10] = [
+            (Slope45UpTile.into(), 4.0, -4.0),
+            (Slope45DownTile.into(), -4.0, 4.0),
+            (Slope22UpLowerTile.into(), 4.0, 0.0),
+            (Slope22UpUpperTile.into(), 0.0, -4.0),
+            (Slope22DownUpperTile.into(), -4.0, 0.0),
+            (Slope22DownLowerTile.into(), 0.0, 4.0),
+            (RoundCornerLTile.into(), 4.0, -4.0),
+            (RoundCornerRTile.into(), -4.0, 4.0),
+            (PipeLTile.into(), -4.0, 4.0),
+            (PipeRTile.into(), 4.0, -4.0),
+        ];
+        for (i, &(tile, left, right)) in cases.iter().enumerate() {
+            assert!((surface_height(tile, -4.0) - left).abs() < 0.5, "case {}: {}", i, surface_height(tile, -4.0));
+            assert!((surface_height(tile, 4.0) - right).abs() < 0.5, "case {}: {}", i, surface_height(tile, 4.0));
+        }
+    }
+
+    #[test]
+    fn ground_follows_slope() {
+        let is_slope = |tile| tile_type_enum_eq(tile, Slope22UpLowerTile.into()) || tile_type_enum_eq(tile, Slope22UpUpperTile.into());
+        // the hill in the corridor, right of the checkpoint
+        let (cx, cy) = UniqueItem::CheckpointCorridor.get_pos();
+        let start = (cx..cx + 32).find(|&x| is_slope(World::get_tile((x, cy)))).unwrap();
+
+        let mut player = Player::new();
+        player.respawn((start - 2, cy));
+        let platforms = Platforms::new();
+        for _ in 0..60 {
+            player.control(0, 0);
+            player.simulate_frame(&platforms);
+        }
+        let middle = World::from_world_coords((start, cy)).re + 4.0;
+        let mut on_slope = 0;
+        for _ in 0..120 {
+            player.control(BUTTON_RIGHT, BUTTON_RIGHT);
+            player.simulate_frame(&platforms);
+            if player.grounded && (player.pos.re - middle).abs() < 4.0 {
+                on_slope += 1;
+                let expected = cf32::new(2.0, -1.0).unscale(5.0f32.sqrt());
+                assert!((player.ground_level - expected).norm() < 0.1, "{:?}", player.ground_level);
+            }
+        }
+        assert!(on_slope >= 3, "{}", on_slope);
+        // climbed onto the plateau and went over the rounded corner
+        assert!(player.my_world_coords().0 > start + 6);
+    }
+
+    /// Upper left tile of the first group of tiles in the level satisfying `f`
+    fn find_tile(f: impl Fn(TileTypeEnum) -> bool) -> TilePos {
+        let (max_x, max_y) = World::BOTTOM_RIGHT_TILE;
+        (0..max_y).flat_map(|y| (0..max_x).map(move |x| (x, y)))
+            .find(|&pos| f(World::get_tile(pos)))
+            .unwrap()
+    }
+
+    fn run(player: &mut Player, frames: u32, prev: u8, cur: u8) {
+        for _ in 0..frames {
+            player.control(prev, cur);
+            player.simulate_frame(&Platforms::new());
+        }
+    }
+
+    #[test]
+    fn jumpy_tile_pulses_after_bounce() {
+        let jumpy = find_tile(|t| tile_type_enum_eq(t, JumpyTile.into()));
+        let mut player = Player::new();
+        player.respawn((jumpy.0, jumpy.1 - 4));
+        let platforms = Platforms::new();
+        let mut world = World::new();
+        let mut frames = 0;
+        while player.bounced.is_none() {
+            player.control(0, 0);
+            player.simulate_frame(&platforms);
+            frames += 1;
+            assert!(frames < 120);
+        }
+        let bounced = player.bounced.unwrap();
+        assert!(tile_type_enum_eq(World::get_tile(bounced), JumpyTile.into()));
+        assert!(world.animation_frame(JumpyTile.into(), bounced, 0).is_none());
+
+        world.animate(player.bounced.take());
+        let shown: Vec<_> = (0..20).map(|_| {
+            let frame = world.animation_frame(JumpyTile.into(), bounced, 0).map(|(_, index)| index);
+            world.animate(None);
+            frame
+        }).collect();
+        assert_eq!(shown[..4], [Some(0); 4]);
+        assert_eq!(shown[4..12], [Some(1), Some(1), Some(1), Some(1), Some(2), Some(2), Some(2), Some(2)]);
+        assert_eq!(shown[12..], [None; 8]);
+        // other jumpy tiles do not pulse
+        assert!(world.animation_frame(JumpyTile.into(), (bounced.0 + 1, bounced.1), 0).is_none());
+    }
+
+    #[test]
+    fn decorations_loop() {
+        let world = World::new();
+        let pos = find_tile(|t| tile_type_enum_eq(t, TorchTile.into()));
+        let frames: Vec<_> = (0..300).map(|f| world.animation_frame(TorchTile.into(), pos, f).unwrap().1).collect();
+        assert_eq!(frames[..13], [0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 2]);
+        assert_eq!(frames[18], 0);
+        // keeps its pace past 256 frames
+        assert!(frames.iter().enumerate().all(|(i, &f)| f == i as u32 / 6 % 3));
+        assert!(world.animation_frame(WaterTile.into(), pos, 0).is_some());
+        assert!(world.animation_frame(UsualArea1Tile.into(), pos, 0).is_none());
+    }
+
+    #[test]
+    fn one_way_tile_from_above_only() {
+        let bridge = find_tile(|t| t.one_way());
+        let bridge = (bridge.0 + 2, bridge.1);
+        let top = World::from_world_coords(bridge).im - 4.0;
+        let platforms = Platforms::new();
+
+        // jumping up through it
+        let mut player = Player::new();
+        player.pos = World::from_world_coords(bridge) + cf32::new(0.0, 8.0);
+        player.vel = cf32::new(0.0, -400.0);
+        for _ in 0..20 {
+            player.control(0, 0);
+            player.simulate_frame(&platforms);
+        }
+        assert!(player.pos.im < top);
+
+        // landing on it
+        for _ in 0..100 {
+            player.control(0, 0);
+            player.simulate_frame(&platforms);
+        }
+        assert!(player.grounded && player.pos.im < top);
+
+        // pressing down drops through
+        player.control(0, BUTTON_DOWN);
+        player.simulate_frame(&platforms);
+        for _ in 0..30 {
+            player.control(BUTTON_DOWN, BUTTON_DOWN);
+            player.simulate_frame(&platforms);
+        }
+        assert!(player.pos.im > top + 8.0);
+    }
+
+    #[test]
+    fn water_floats_and_allows_swimming() {
+        let water = find_tile(|t| t.physics().swim);
+        let mut player = Player::new();
+        player.respawn((water.0 + 3, water.1 + 1));
+        let start = player.pos;
+        run(&mut player, 1, 0, 0);
+        assert!(player.physics.swim);
+        run(&mut player, 30, 0, 0);
+        assert!(player.pos.im < start.im);
+
+        let floating = player.pos;
+
+        // swimming down and to the side, no ground pound in water
+        assert!(!player.grounded);
+        player.status.touch(UniqueItem::FeatureGroundPound);
+        run(&mut player, 1, 0, BUTTON_DOWN);
+        assert!(!player.pounding);
+        run(&mut player, 20, BUTTON_DOWN | BUTTON_LEFT, BUTTON_DOWN | BUTTON_LEFT);
+        assert!(player.pos.im > floating.im && player.pos.re < floating.re - 2.0);
+    }
+
+    #[test]
+    fn low_gravity_falls_slower() {
+        let zone = find_tile(|t| t.physics().gravity < 1.0 && t.physics().gravity > 0.0);
+        let mut player = Player::new();
+        player.respawn((zone.0 + 2, zone.1));
+        run(&mut player, 20, 0, 0);
+        let low = player.pos.im - World::from_world_coords(zone).im;
+
+        player.respawn((zone.0 + 2, zone.1 - 4));
+        let start = player.pos.im;
+        run(&mut player, 20, 0, 0);
+        assert!(low > 0.0 && low < 0.5 * (player.pos.im - start));
+    }
+
+    #[test]
+    fn conveyor_carries_player() {
+        for dir in [1.0, -1.0] {
+            let conveyor = find_tile(|t| t.physics().push.re.to_f32() * dir > 0.0);
+            let mut player = Player::new();
+            // start near the end the belt carries away from
+            let start = if dir > 0.0 { conveyor.0 + 1 } else { conveyor.0 + 3 };
+            player.respawn((start, conveyor.1 - 1));
+            run(&mut player, 30, 0, 0);
+            assert!(player.grounded);
+            let start = player.pos;
+            run(&mut player, 30, 0, 0);
+            assert!((player.pos.re - start.re) * dir > 4.0);
+        }
+    }
+
+    #[test]
+    fn no_tunnelling_at_extreme_speeds() {
+        let (max_x, max_y) = World::BOTTOM_RIGHT_TILE;
+        for dir in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            // a square tile with free space in front of it
+            let tile_at = |(x, y): TilePos, k: i32| World::get_tile(((x as i32 - k * dir.0) as u16, (y as i32 - k * dir.1) as u16));
+            let wall = (4..max_y - 4).flat_map(|y| (4..max_x - 4).map(move |x| (x, y)))
+                .find(|&pos| core::ptr::eq(tile_at(pos, 0).collision_configuration(), &SQUARE_TILE[..])
+                    && (1..=4).all(|k| tile_at(pos, k).collision_configuration().is_empty()))
+                .unwrap();
+            let dir = cf32::new(dir.0 as f32, dir.1 as f32);
+            let wall_center = World::from_world_coords(wall);
+
+            for speed in [3000.0, 10000.0, 30000.0] {
+                let mut player = Player::new();
+                player.respawn(World::to_world_coords(wall_center - 24.0 * dir));
+                player.vel = speed * dir;
+                for _ in 0..3 {
+                    run(&mut player, 1, 0, 0);
+                    // never got into the wall tile
+                    assert!(((player.pos - wall_center) / dir).re < -4.0, "{:?} at speed {}", dir, speed);
+                }
+            }
+        }
+    }
+}
@@ -1,52 +1,129 @@
-use crate::{LEVEL, TilePos, camera::Camera, cf32, sprites::{CRATE, INFOBOX1, INFOBOX2, STAR1, STAR2}, utils::{UfmtBuf, draw_colours}, wasm4::{SCREEN_SIZE, blit, rect, text}, world::World};
+use crate::{Scalar, LEVEL, abilities::Ability, TilePos, camera::Camera, sprites::{CHECKPOINT, CRATE, INFOBOX1, INFOBOX2, STAR1, STAR2}, utils::{ConstMsg, UfmtBuf, draw_colours}, wasm4::{SCREEN_SIZE, blit, rect, text}, world::World};
 
-use enum_iterator::IntoEnumIterator;
-
-#[derive(variant_count::VariantCount, PartialEq, Eq, Copy, Clone, enum_iterator::IntoEnumIterator)]
+#[derive(variant_count::VariantCount, PartialEq, Eq, Hash, Copy, Clone, enum_iterator::IntoEnumIterator)]
 pub enum UniqueItem {
     PlayerStart,
+    CheckpointCave,
+    CheckpointCorridor,
     InfoWelcome,
     FeatureSmallSize,
+    FeatureDoubleJump,
+    FeatureWallCling,
+    FeatureGroundPound,
     CrateLog,
+    CrateSerde,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum UniqueItemType {
-    Other,
+    /// Player respawns at the last touched one
+    Checkpoint,
     Infobox,
     Feature,
     Crate,
 }
 
+impl UniqueItemType {
+    pub const ALL: [UniqueItemType; 4] = [UniqueItemType::Checkpoint, UniqueItemType::Infobox, UniqueItemType::Feature, UniqueItemType::Crate];
+
+    /// Label in the collection rate stats, for categories the player has to collect to win
+    pub const fn stats_label(self) -> Option<&'static str> {
+        match self {
+            UniqueItemType::Checkpoint => None,
+            UniqueItemType::Infobox => Some("I"),
+            UniqueItemType::Feature => Some("F"),
+            UniqueItemType::Crate => Some("C"),
+        }
+    }
+}
+
+/// Everything about a unique item except its position, which comes from the level
+pub struct ItemInfo {
+    pub item: UniqueItem,
+    /// For compile-time error messages
+    pub name: &'static str,
+    pub category: UniqueItemType,
+    /// Shown when the player is at an infobox or a crate
+    pub text: &'static str,
+    /// Normal and blinking sprite. Items without sprites are invisible.
+    pub sprites: Option<(&'static [u8; 8], &'static [u8; 8])>,
+}
+
+/// `ItemInfo` of a `UniqueItem` variant, named after it
+macro_rules! item_info {
+    ($item:ident, $category:ident, $text:expr, $sprites:expr) => {
+        ItemInfo { item: UniqueItem::$item, name: stringify!($item), category: UniqueItemType::$category, text: $text, sprites: $sprites }
+    };
+}
+
+/// Each `UniqueItem` must be listed here exactly once and in declaration order, the level compiler checks that
+pub static ITEMS: &[ItemInfo] = &[
+    item_info!(PlayerStart, Checkpoint, "", None),
+    item_info!(CheckpointCave, Checkpoint, "", Some((&CHECKPOINT, &CHECKPOINT))),
+    item_info!(CheckpointCorridor, Checkpoint, "", Some((&CHECKPOINT, &CHECKPOINT))),
+    item_info!(
+        InfoWelcome,
+        Infobox,
+        "Welcome to\nRustMW\n\nGame goal is to\ncollect all the:\n\n* infoboxes\n* features\n* crates\n\nCollection rate:",
+        Some((&INFOBOX1, &INFOBOX2))
+    ),
+    item_info!(FeatureSmallSize, Feature, "", Some((&STAR1, &STAR2))),
+    item_info!(FeatureDoubleJump, Feature, "", Some((&STAR1, &STAR2))),
+    item_info!(FeatureWallCling, Feature, "", Some((&STAR1, &STAR2))),
+    item_info!(FeatureGroundPound, Feature, "", Some((&STAR1, &STAR2))),
+    item_info!(CrateLog, Crate, "log", Some((&CRATE, &CRATE))),
+    item_info!(CrateSerde, Crate, "serde", Some((&CRATE, &CRATE))),
+];
+
+/// Compile-time check that every item is listed in the table (i.e. `ITEMS`) exactly once, in declaration order,
+/// so that it can be indexed by the item
+pub const fn check_items_table(table: &[ItemInfo]) {
+    let mut x = 0;
+    while x < table.len() {
+        if table[x].item as usize != x {
+            ConstMsg::new("Unique item ").s(table[x].name).s(" is listed in unique_items::ITEMS at position ").n(x)
+                .s(": list every item once, in declaration order").panic();
+        }
+        x += 1;
+    }
+    if table.len() < UniqueItem::VARIANT_COUNT {
+        ConstMsg::new("Unique item #").n(table.len()).s(" (in declaration order) has no category: list it in unique_items::ITEMS").panic();
+    }
+}
+
+/// Entry of `ITEMS` for the item with given position in `UniqueItem` declaration
+pub const fn info_by_ordinal(x: usize) -> &'static ItemInfo {
+    &ITEMS[x]
+}
+
 impl UniqueItem {
+    pub const fn info(self) -> &'static ItemInfo {
+        info_by_ordinal(self as usize)
+    }
+
+    pub const fn name(self) -> &'static str {
+        self.info().name
+    }
+
     pub const fn get_pos(self) -> TilePos {
         LEVEL.unique_item_pos(self)
     }
 
     pub const fn visible(self) -> bool {
-        match self.r#type() {
-            UniqueItemType::Other => false,
-            UniqueItemType::Infobox => true,
-            UniqueItemType::Feature => true,
-            UniqueItemType::Crate => true,
-        }
+        self.info().sprites.is_some()
     }
 
     pub const fn r#type(self) -> UniqueItemType {
-        match self as usize {
-            x if x >= UniqueItem::InfoWelcome as usize && x < UniqueItem::FeatureSmallSize as usize => UniqueItemType::Infobox,
-            x if x >= UniqueItem::FeatureSmallSize as usize && x < UniqueItem::CrateLog as usize => UniqueItemType::Feature,
-            x if x >= UniqueItem::CrateLog as usize => UniqueItemType::Crate,
-            _ => UniqueItemType::Other,
-        }
+        self.info().category
+    }
+
+    /// Whether touching the item sets the respawn position
+    pub const fn is_checkpoint(self) -> bool {
+        matches!(self.r#type(), UniqueItemType::Checkpoint)
     }
 
     pub const fn text(self) -> &'static str {
-        use UniqueItem::*;
-        match self {
-            InfoWelcome => "Welcome to\nRustMW\n\nGame goal is to\ncollect all the:\n\n* infoboxes\n* features\n* crates\n\nCollection rate:",
-            CrateLog => "log",
-            _ => "",
-        }
+        self.info().text
     }
 }
 
@@ -60,13 +137,25 @@ const fn bitfield_bitmask(x: usize) -> u8 {
     0x1 << (x & 7)
 }
 
+#[derive(Clone, Copy)]
 pub struct TouchedUniqueItems {
-    data: [u8; bitfield_len(UniqueItem::VARIANT_COUNT)],
+    data: [u8; TouchedUniqueItems::BYTES],
 }
 
 impl TouchedUniqueItems {
+    /// Size of the bitfield when stored in a save file
+    pub const BYTES: usize = bitfield_len(UniqueItem::VARIANT_COUNT);
+
     pub const fn new() -> TouchedUniqueItems {
-        TouchedUniqueItems { data: [0;  bitfield_len(UniqueItem::VARIANT_COUNT)]}
+        TouchedUniqueItems { data: [0; TouchedUniqueItems::BYTES]}
+    }
+
+    pub const fn from_bytes(data: [u8; TouchedUniqueItems::BYTES]) -> TouchedUniqueItems {
+        TouchedUniqueItems { data }
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; TouchedUniqueItems::BYTES] {
+        &self.data
     }
 
     pub fn touch(&mut self, item: UniqueItem) {
@@ -80,62 +169,128 @@ impl TouchedUniqueItems {
     }
 }
 
-fn draw_stats(status: &TouchedUniqueItems) {
-    let total_infos = UniqueItem::FeatureSmallSize as usize - UniqueItem::InfoWelcome as usize;
-    let total_features = UniqueItem::CrateLog as usize - UniqueItem::FeatureSmallSize as usize;
-    let total_crates = UniqueItem::VARIANT_COUNT - UniqueItem::CrateLog as usize;
-
-    let mut touched_infos = 0usize;
-    let mut touched_features = 0usize;
-    let mut touched_crates = 0usize;
-
-    for item in UniqueItem::into_enum_iter() {
-        if status.is_touched(item) {
-            match item.r#type() {
-                UniqueItemType::Other => (),
-                UniqueItemType::Infobox => touched_infos+=1,
-                UniqueItemType::Feature => touched_features+=1,
-                UniqueItemType::Crate => touched_crates+=1,
+/// Rooms the player has been in, for the map viewer's fog of war
+#[derive(Clone, Copy)]
+pub struct VisitedRooms {
+    data: [u8; VisitedRooms::BYTES],
+}
+
+impl VisitedRooms {
+    /// Width of the world in rooms
+    const ROW: usize = LEVEL.size().0 as usize / 16;
+    const ROOMS: usize = VisitedRooms::ROW * (LEVEL.size().1 as usize / 16);
+    /// Size of the bitfield when stored in a save file
+    pub const BYTES: usize = bitfield_len(VisitedRooms::ROOMS);
+
+    pub const fn new() -> VisitedRooms {
+        VisitedRooms { data: [0; VisitedRooms::BYTES]}
+    }
+
+    pub const fn from_bytes(data: [u8; VisitedRooms::BYTES]) -> VisitedRooms {
+        VisitedRooms { data }
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; VisitedRooms::BYTES] {
+        &self.data
+    }
+
+    const fn room_index((x, y): TilePos) -> Option<usize> {
+        let (rx, ry) = ((x >> 4) as usize, (y >> 4) as usize);
+        if rx >= VisitedRooms::ROW || ry * VisitedRooms::ROW >= VisitedRooms::ROOMS {
+            return None;
+        }
+        Some(ry * VisitedRooms::ROW + rx)
+    }
+
+    /// Mark the room containing the tile as visited. Returns whether it was not visited before.
+    pub fn visit(&mut self, tile: TilePos) -> bool {
+        match VisitedRooms::room_index(tile) {
+            Some(x) => {
+                let new = self.data[bitfield_byte(x)] & bitfield_bitmask(x) == 0;
+                self.data[bitfield_byte(x)] |= bitfield_bitmask(x);
+                new
             }
+            None => false,
+        }
+    }
+
+    /// Whether the room containing the tile was visited
+    pub fn is_visited(&self, tile: TilePos) -> bool {
+        match VisitedRooms::room_index(tile) {
+            Some(x) => (self.data[bitfield_byte(x)] & bitfield_bitmask(x)) != 0,
+            None => false,
         }
     }
+}
+
+impl Default for VisitedRooms {
+    fn default() -> Self {
+        VisitedRooms::new()
+    }
+}
 
-    let mut buf = UfmtBuf::<14>::new();
-    let _ = ufmt::uwrite!(buf, "I: {} of {}", touched_infos, total_infos);
-    text(buf.as_str(), 14, 108);
+fn draw_stats(status: &TouchedUniqueItems) {
+    let mut all_collected = true;
+    let mut y = 108;
+
+    for category in UniqueItemType::ALL {
+        let label = match category.stats_label() {
+            Some(x) => x,
+            None => continue,
+        };
+        let total = ITEMS.iter().filter(|x| x.category == category).count();
+        let touched = ITEMS.iter().filter(|x| x.category == category && status.is_touched(x.item)).count();
+        if touched != total {
+            all_collected = false;
+        }
 
-    let mut buf = UfmtBuf::<14>::new();
-    let _ = ufmt::uwrite!(buf, "F: {} of {}", touched_features, total_features);
-    text(buf.as_str(), 14, 118);
+        let mut buf = UfmtBuf::<14>::new();
+        let _ = ufmt::uwrite!(buf, "{}: {} of {}", label, touched, total);
+        text(buf.as_str(), 14, y);
 
-    let mut buf = UfmtBuf::<14>::new();
-    let _ = ufmt::uwrite!(buf, "C: {} of {}", touched_crates, total_crates);
-    text(buf.as_str(), 14, 128);
+        if category == UniqueItemType::Feature {
+            let mut buf = UfmtBuf::<{ Ability::ALL.len() }>::new();
+            for ability in Ability::ALL {
+                let _ = ufmt::uwrite!(buf, "{}", if status.is_touched(ability.feature()) { ability.letter() } else { "-" });
+            }
+            text(buf.as_str(), 14 + 8 * 11, y);
+        }
+        y += 10;
+    }
 
-    if touched_infos == total_infos && touched_features == total_features && touched_crates == total_crates {
+    if all_collected {
         draw_colours(4, 0, 0, 0);
-        text("You won.", 48, 138);
+        text("You won.", 48, y);
     }
 
 }
 
 
-pub fn draw_unique(item: UniqueItem, frame: u8, player_pos: TilePos, cam: &Camera, inhibit_drawing_player: &mut bool, status: &mut TouchedUniqueItems) {
+pub struct Touch {
+    /// The player is at the item during this frame
+    pub now: bool,
+    /// The item got touched for the first time during this call
+    pub first_time: bool,
+}
+
+pub fn draw_unique(item: UniqueItem, frame: u8, player_pos: TilePos, cam: &Camera, inhibit_drawing_player: &mut bool, status: &mut TouchedUniqueItems) -> Touch {
     let posraw = LEVEL.unique_item_pos(item);
     let pos = World::from_world_coords(posraw);
 
     let touched_now = posraw == player_pos;
     let mut touched = status.is_touched(item);
-    if touched_now && !touched {
+    let newly_touched = touched_now && !touched;
+    if newly_touched {
         status.touch(item);
         touched = true;
     } 
+    let result = Touch { now: touched_now, first_time: newly_touched };
 
-    let center = pos - cam.pos + cf32::new(0.5, 0.5) * SCREEN_SIZE as f32;
+    let center = cam.to_screen(pos);
     if center.re < 4.5 || center.im < 4.5 || center.re + 4.5 > SCREEN_SIZE as f32  || center.im + 4.5 >= SCREEN_SIZE as f32 {
-        return;
+        return result;
     }
-    let (x, y) = (center.re as i32, center.im as i32);
+    let (x, y) = (center.re.to_f32() as i32, center.im.to_f32() as i32);
 
     let mut blinker = frame % 60 < 30;
 
@@ -176,16 +331,54 @@ pub fn draw_unique(item: UniqueItem, frame: u8, player_pos: TilePos, cam: &Camer
     }
 
     if *inhibit_drawing_player {
-        return;
+        return result;
     }
 
     use UniqueItemType::*;
-    match (r#type, touched, blinker) {
-        (Infobox, _, false) => blit(&INFOBOX1, x-4, y-4, 8, 8, 0),
-        (Infobox, _, true) => blit(&INFOBOX2, x-4, y-4, 8, 8, 0),
-        (Feature, _, false) => blit(&STAR1, x-4, y-4, 8, 8, 0),
-        (Feature, _, true) => blit(&STAR2, x-4, y-4, 8, 8, 0),
-        (Crate,   _, _) => blit(&CRATE, x-4, y-4, 8, 8, 0),
-        _ => (),
+    match (item.info().sprites, blinker) {
+        (Some((sprite, _)), false) => blit(sprite, x-4, y-4, 8, 8, 0),
+        (Some((_, sprite)), true) => blit(sprite, x-4, y-4, 8, 8, 0),
+        (None, _) => (),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories_come_from_table() {
+        assert!(UniqueItem::CheckpointCave.is_checkpoint());
+        assert!(matches!(UniqueItem::CrateSerde.r#type(), UniqueItemType::Crate));
+        assert!(!UniqueItem::PlayerStart.visible());
+        assert_eq!(UniqueItem::CrateLog.name(), "CrateLog");
+        assert_eq!(ITEMS.iter().filter(|x| x.category == UniqueItemType::Crate).count(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unique item #1 (in declaration order) has no category")]
+    fn item_without_category() {
+        check_items_table(&ITEMS[..1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unique item PlayerStart is listed in unique_items::ITEMS at position 1")]
+    fn item_listed_twice() {
+        static TWICE: [ItemInfo; 2] = [
+            item_info!(PlayerStart, Checkpoint, "", None),
+            item_info!(PlayerStart, Checkpoint, "", None),
+        ];
+        check_items_table(&TWICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unique item CheckpointCave is listed in unique_items::ITEMS at position 0")]
+    fn items_out_of_order() {
+        static SWAPPED: [ItemInfo; 2] = [
+            item_info!(CheckpointCave, Checkpoint, "", None),
+            item_info!(PlayerStart, Checkpoint, "", None),
+        ];
+        check_items_table(&SWAPPED);
     }
 }
@@ -21,6 +21,34 @@ macro_rules! tile_types_mapping {
     };
 }
 
+#[macro_export]
+macro_rules! platforms_mapping {
+    ($(($kind:ident $chr:ident))*) => {
+        [
+            $(
+                $crate::MappingBetweenCharAndPlatform {
+                    chr: stringify!($chr).as_bytes()[0],
+                    kind: &$crate::platforms::$kind,
+                }
+            ),*
+        ]
+    };
+}
+
+#[macro_export]
+macro_rules! enemies_mapping {
+    ($(($kind:ident $chr:ident))*) => {
+        [
+            $(
+                $crate::MappingBetweenCharAndEnemy {
+                    chr: stringify!($chr).as_bytes()[0],
+                    kind: $crate::enemies::EnemyKind::$kind,
+                }
+            ),*
+        ]
+    };
+}
+
 #[macro_export]
 macro_rules! tile_type {
     ($item:ident) => {crate::tiles::TileTypeEnum::$item(crate::tiles::$item)}
@@ -74,6 +102,84 @@ macro_rules! unique_items_mapping {
 }
 
 
+/// Error message builder for `const fn`s, where `format!` is unavailable. Overlong messages get truncated.
+pub struct ConstMsg {
+    buf: [u8; 256],
+    len: usize,
+}
+
+impl ConstMsg {
+    pub const fn new(s: &str) -> ConstMsg {
+        ConstMsg { buf: [0; 256], len: 0 }.s(s)
+    }
+
+    const fn byte(mut self, b: u8) -> ConstMsg {
+        if self.len < self.buf.len() {
+            self.buf[self.len] = b;
+            self.len += 1;
+        }
+        self
+    }
+
+    /// Append a string
+    pub const fn s(mut self, s: &str) -> ConstMsg {
+        let s = s.as_bytes();
+        let mut i = 0;
+        while i < s.len() {
+            self = self.byte(s[i]);
+            i += 1;
+        }
+        self
+    }
+
+    /// Append a number
+    pub const fn n(mut self, mut x: usize) -> ConstMsg {
+        let mut digits = [0u8; 20];
+        let mut len = 0;
+        loop {
+            digits[len] = b'0' + (x % 10) as u8;
+            len += 1;
+            x /= 10;
+            if x == 0 { break; }
+        }
+        while len > 0 {
+            len -= 1;
+            self = self.byte(digits[len]);
+        }
+        self
+    }
+
+    /// Append a quoted character of level or sprite text. Non-printable ones are shown as a number.
+    pub const fn c(self, c: u8) -> ConstMsg {
+        if c >= 0x20 && c < 0x7F {
+            self.byte(b'\'').byte(c).byte(b'\'')
+        } else {
+            self.s("byte ").n(c as usize)
+        }
+    }
+
+    /// Append tile position as `(x, y)`
+    pub const fn pos(self, (x, y): TilePos) -> ConstMsg {
+        self.s("(").n(x as usize).s(", ").n(y as usize).s(")")
+    }
+
+    pub const fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.buf.split_at(self.len).0) {
+            Ok(s) => s,
+            Err(_) => "(malformed error message)",
+        }
+    }
+
+    pub const fn panic(self) -> ! {
+        panic!("{}", self.as_str())
+    }
+}
+
+/// Error inside `|`-delimited text of sprites and rooms. `line` and `column` are zero-based.
+pub(crate) const fn text_error(what: &str, line: usize, column: usize, chr: u8, msg: &str) -> ! {
+    ConstMsg::new(what).s(" line ").n(line + 1).s(", column ").n(column + 1).s(", character ").c(chr).s(": ").s(msg).panic()
+}
+
 pub const fn sprite8x8(s: &'static[u8]) -> [u8; 8] {
     let mut buf = [0u8; 8];
     let mut byteidx = 0;
@@ -89,27 +195,30 @@ pub const fn sprite8x8(s: &'static[u8]) -> [u8; 8] {
                 within_sprite_area = true;
                 bitidx = 0;
                 column = -1;
+                if byteidx >= 8 {
+                    text_error("8x8 sprite", byteidx, 0, chr, "there must be exactly 8 lines");
+                }
             }
             b'|' if within_sprite_area => {
                 within_sprite_area = false;
                 if column != 15 {
-                    b"8x8 sprites much have exactly 15 characters between | markers"[999];
+                    text_error("8x8 sprite", byteidx, column as usize, chr, "there must be exactly 15 characters between | markers");
                 }
                 byteidx += 1;
             }
             b' ' if column % 2 == 1 => (),
-            b'X' if column % 2 == 1 => {
-                b"Each odd column between markers must be empty"[999];
+            _ if within_sprite_area && column % 2 == 1 => {
+                text_error("8x8 sprite", byteidx, column as usize, chr, "each odd column between markers must be empty");
             }
-            b'X' | b'x' if column % 2 == 0 => {
+            b'X' | b'x' if column % 2 == 0 && column < 15 => {
                 bitidx += 1;
             }
-            b' ' | b'.' if column % 2 == 0 => {
+            b' ' | b'.' if column % 2 == 0 && column < 15 => {
                 buf[byteidx] |= 1 << (7 - bitidx);
                 bitidx += 1;
             }
             _ if within_sprite_area => {
-                b"Unknown character within marker zone"[999];
+                text_error("8x8 sprite", byteidx, column as usize, chr, "unknown character within marker zone, or the line is too long");
             }
             _ => (),
         }
@@ -119,7 +228,7 @@ pub const fn sprite8x8(s: &'static[u8]) -> [u8; 8] {
         }
     }
     if byteidx != 8 {
-        b"Unfinished sprite"[999];
+        ConstMsg::new("8x8 sprite has ").n(byteidx).s(" complete lines instead of 8").panic();
     }
     buf
 }
@@ -140,26 +249,29 @@ pub const fn sprite16x16(s: &'static [u8]) -> [u8; 32] {
                 within_sprite_area = true;
                 bitidx = 0;
                 column = -1;
+                if byteidx >= 32 {
+                    text_error("16x16 sprite", byteidx / 2, 0, chr, "there must be exactly 16 lines");
+                }
             }
             b'|' if within_sprite_area => {
                 within_sprite_area = false;
                 if column != 31 {
-                    b"8x8 sprites much have exactly 31 characters between | markers"[999];
+                    text_error("16x16 sprite", byteidx / 2, column as usize, chr, "there must be exactly 31 characters between | markers");
                 }
             }
             b' ' if column % 2 == 1 => (),
-            b'X' if column % 2 == 1 => {
-                b"Each odd column between markers must be empty"[999];
+            _ if within_sprite_area && column % 2 == 1 => {
+                text_error("16x16 sprite", byteidx / 2, column as usize, chr, "each odd column between markers must be empty");
             }
-            b'X' | b'x' if column % 2 == 0 => {
+            b'X' | b'x' if column % 2 == 0 && column < 31 => {
                 bitidx += 1;
             }
-            b' ' | b'.' if column % 2 == 0 => {
+            b' ' | b'.' if column % 2 == 0 && column < 31 => {
                 buf[byteidx] |= 1 << (7 - bitidx);
                 bitidx += 1;
             }
             _ if within_sprite_area => {
-                b"Unknown character within marker zone"[999];
+                text_error("16x16 sprite", byteidx / 2, column as usize, chr, "unknown character within marker zone, or the line is too long");
             }
             _ => (),
         }
@@ -173,7 +285,72 @@ pub const fn sprite16x16(s: &'static [u8]) -> [u8; 32] {
         }
     }
     if byteidx != 32 {
-        b"Unfinished sprite"[999];
+        ConstMsg::new("16x16 sprite has ").n(byteidx / 2).s(" complete lines instead of 16").panic();
+    }
+    buf
+}
+
+/// 1BPP sprite of any size, in the format of `sprite8x8`. `N` is the size of the data in bytes.
+/// Sprite sheets are sprites with frames side by side, see `sprites::SpriteSheet`.
+pub const fn sprite1bpp<const N: usize>(width: usize, height: usize, s: &'static [u8]) -> [u8; N] {
+    sprite_bits("1BPP sprite", width, height, 1, s)
+}
+
+/// 2BPP sprite of any size. Glyphs are `.` (or space) for colour 0, `-` for 1, `o` for 2 and `X` for 3.
+pub const fn sprite2bpp<const N: usize>(width: usize, height: usize, s: &'static [u8]) -> [u8; N] {
+    sprite_bits("2BPP sprite", width, height, 2, s)
+}
+
+const fn sprite_bits<const N: usize>(what: &str, width: usize, height: usize, bpp: usize, s: &'static [u8]) -> [u8; N] {
+    let size = (width * height * bpp).div_ceil(8);
+    if N != size {
+        ConstMsg::new(what).s(" of ").n(width).s("x").n(height).s(" takes ").n(size).s(" bytes, not ").n(N).panic();
+    }
+    let mut buf = [0u8; N];
+    let mut lineidx = 0;
+    let mut column = 0;
+    let mut within_sprite_area = false;
+
+    let mut i = 0;
+    while i < s.len() {
+        let chr = s[i];
+        if chr == b'|' {
+            if within_sprite_area {
+                if column != 2 * width - 1 {
+                    text_error(what, lineidx, column, chr, "there must be one glyph for each pixel, separated by spaces");
+                }
+                lineidx += 1;
+            } else if lineidx >= height {
+                text_error(what, lineidx, 0, chr, "too many lines");
+            }
+            within_sprite_area = !within_sprite_area;
+            column = 0;
+        } else if within_sprite_area {
+            if column % 2 == 1 {
+                if chr != b' ' {
+                    text_error(what, lineidx, column, chr, "each odd column between markers must be empty");
+                }
+            } else {
+                if column >= 2 * width - 1 {
+                    text_error(what, lineidx, column, chr, "the line is too long");
+                }
+                // like in `sprite8x8`, `X` is the colour 0 of 1BPP sprites
+                let value = match (bpp, chr) {
+                    (1, b'X' | b'x') | (2, b'.' | b' ') => 0,
+                    (1, b'.' | b' ') | (2, b'-') => 1,
+                    (2, b'o') => 2,
+                    (2, b'X') => 3,
+                    _ => text_error(what, lineidx, column, chr, "unknown glyph"),
+                };
+                let bit = (lineidx * width + column / 2) * bpp;
+                buf[bit / 8] |= value << (8 - bpp - bit % 8);
+            }
+            column += 1;
+        }
+        i += 1;
+    }
+    if lineidx != height || within_sprite_area {
+        ConstMsg::new(what).s(" has ").n(lineidx).s(" complete lines instead of ").n(height).panic();
     }
     buf
 }
@@ -195,12 +372,12 @@ pub const fn room16x16(s: &'static [u8]) -> [u32; 16] {
                 if within_room_area {
                     // began the line
                     if lineidx >= 8 {
-                        b"There must by exactly 8 lines in each room"[999];
+                        text_error("Room", lineidx, 0, chr, "there must be exactly 8 lines in each room");
                     }
                 } else {
                     // finished the line
                     if cellidx != 16 {
-                        b"Each line of the room but by eactly 16 characters long"[999];
+                        text_error("Room", lineidx, cellidx, chr, "each line of the room must be exactly 16 characters long");
                     }
                     cellidx = 0;
                     lineidx += 1;
@@ -208,7 +385,7 @@ pub const fn room16x16(s: &'static [u8]) -> [u32; 16] {
             }
             _ if within_room_area => {
                 if cellidx >= 16 {
-                    b"Each line of the room but by eactly 16 characters long"[999];
+                    text_error("Room", lineidx, cellidx, chr, "each line of the room must be exactly 16 characters long");
                 }
                 match chr {
                     b' ' => {
@@ -228,7 +405,7 @@ pub const fn room16x16(s: &'static [u8]) -> [u32; 16] {
                         buf[2*lineidx+1] |= 0b01 << (cellidx*2);
                     }
                     _ => {
-                        b"Undefined character encountered within the room area"[999];
+                        text_error("Room", lineidx, cellidx, chr, "undefined character within the room area");
                     }
                 }
                 cellidx+=1;
@@ -238,63 +415,100 @@ pub const fn room16x16(s: &'static [u8]) -> [u32; 16] {
         i += 1;
     }  
     if lineidx != 8 {
-        b"There must by exactly 8 lines in each room"[999];
+        ConstMsg::new("Room has ").n(lineidx).s(" lines instead of 8").panic();
     }
 
     buf
 }
 
-use crate::{Area, AreaSource, CharDescription, Level, LowlevelCellType, MAX_UNIQUE_ITEMS_PER_ROOM, MAX_UNIQUE_ITEM_POSITIONS, MappingBetweenCharAndItem, MappingBetweenCharAndTileType, RoomBlock, RoomMetadata, TilePos, UniqueItem, UniqueItemPosition, UniqueItemPositionLowlevel, UniqueItemPositions, UniqueItemsInThisRoom, level, tiles::{TileTypeEnum, tile_type_enum_eq}}; 
+use crate::{AREA_SIZE, Area, AreaSource, CharDescription, Level, LowlevelCellType, MAX_ENEMIES_PER_AREA, MAX_PLATFORMS_PER_AREA, MAX_UNIQUE_ITEMS_PER_ROOM, MAX_UNIQUE_ITEM_POSITIONS, EnemiesInThisArea, MappingBetweenCharAndEnemy, MappingBetweenCharAndItem, MappingBetweenCharAndPlatform, MappingBetweenCharAndTileType, PlatformsInThisArea, RoomBlock, RoomMetadata, camera::CameraMode, TilePos, UniqueItem, UniqueItemPosition, UniqueItemPositionLowlevel, UniqueItemPositions, UniqueItemsInThisRoom, PlacedArea, level, unique_items, platforms::{PlatformKind, PlatformPosition}, enemies::{EnemyKind, EnemyPosition}, tiles::{TileTypeEnum, tile_type_enum_eq}}; 
 
-const fn lookup_char<const N:usize>(c: u8, char_lookup:[CharDescription; N]) -> CharDescription {
+const fn lookup_char<const N:usize>(c: u8, char_lookup:[CharDescription; N]) -> Option<CharDescription> {
     let mut j = 0;
     while j < char_lookup.len() {
         if char_lookup[j].chr == c {
-            return char_lookup[j];
+            return Some(char_lookup[j]);
         }
         j+=1;
     }
-    loop{
-        b"Special character type not found"[999];
-    }
+    None
 }
 
-const fn lookup_tt<const N:usize>(c: u8, lookup:[MappingBetweenCharAndTileType; N]) -> TileTypeEnum {
+const fn lookup_tt<const N:usize>(c: u8, lookup:[MappingBetweenCharAndTileType; N]) -> Option<TileTypeEnum> {
     let mut j = 0;
     while j < lookup.len() {
         if lookup[j].chr == c {
-            return lookup[j].tt;
+            return Some(lookup[j].tt);
+        }
+        j+=1;
+    }
+    None
+}
+
+const fn lookup_platform<const N:usize>(c: u8, lookup: [MappingBetweenCharAndPlatform; N]) -> Option<&'static PlatformKind> {
+    let mut j = 0;
+    while j < N {
+        if lookup[j].chr == c {
+            return Some(lookup[j].kind);
         }
         j+=1;
     }
-    loop{
-        b"Tile type not found for this charater"[999];
+    None
+}
+
+const fn lookup_enemy<const N:usize>(c: u8, lookup: [MappingBetweenCharAndEnemy; N]) -> Option<EnemyKind> {
+    let mut j = 0;
+    while j < N {
+        if lookup[j].chr == c {
+            return Some(lookup[j].kind);
+        }
+        j+=1;
     }
+    None
 }
 
-const fn lookup_unique<const N:usize>(c: u8, lookup:[MappingBetweenCharAndItem; N]) -> (UniqueItem, bool) {
+const fn lookup_unique<const N:usize>(c: u8, lookup:[MappingBetweenCharAndItem; N]) -> Option<(UniqueItem, bool)> {
     let mut j = 0;
     while j < lookup.len() {
         if lookup[j].chr == c {
-            return (lookup[j].item, lookup[j].priority);
+            return Some((lookup[j].item, lookup[j].priority));
         }
         j+=1;
     }
-    loop{
-        b"Encountered unique item character that is not mapped to UniquItem"[999];
-    }
+    None
 }
 
-const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C,T,I>) 
--> 
-(   RoomBlock, 
-    [Option<UniqueItemPositionLowlevel>; 32],
-    [RoomMetadata; 32],
-    [UniqueItemsInThisRoom; 32],
-) {
+/// Error at specific character of area text. `line` and `column` are zero-based indexes within `|` markers.
+const fn area_error(area: &str, line: u16, column: u16, chr: u8, msg: &str) -> ! {
+    let room = (line / 8) * 8 + column / 16;
+    ConstMsg::new("Area ").s(area)
+        .s(", line ").n(line as usize + 1)
+        .s(", column ").n(column as usize + 1)
+        .s(" (room ").n(room as usize)
+        .s("), character ").c(chr)
+        .s(": ").s(msg)
+        .panic()
+}
+
+/// Result of parsing the text of an area, before unique item characters are resolved
+struct ParsedArea {
+    rooms: RoomBlock,
+    /// Unique item characters and their positions within the area
+    specials: [Option<UniqueItemPositionLowlevel>; 32],
+    meta: [RoomMetadata; 32],
+    uniques: [UniqueItemsInThisRoom; 32],
+    platforms: PlatformsInThisArea,
+    enemies: EnemiesInThisArea,
+}
+
+const fn makearea<const C:usize, const T:usize, const I:usize, const P:usize, const E:usize>(src: AreaSource<C,T,I,P,E>) -> ParsedArea {
     let mut buf = [[0u32; 16]; 32];
     let mut special_positions = [None; 32];
     let mut special_position_index = 0;
+    let mut platforms = [None; MAX_PLATFORMS_PER_AREA];
+    let mut platform_index = 0;
+    let mut enemies = [None; MAX_ENEMIES_PER_AREA];
+    let mut enemy_index = 0;
 
     let mut lineidx = 0;
     let mut cellidx = 0;
@@ -309,7 +523,13 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
         block_type_x: Some(src.solid_tile_style),
         block_type_a: None,
         block_type_b: None,
+        camera: CameraMode::Follow,
     }; 32];
+    let mut r = 0;
+    while r < 32 {
+        meta[r].camera = src.camera[r];
+        r += 1;
+    }
 
     let mut uniques = [[None; MAX_UNIQUE_ITEMS_PER_ROOM]; 32];
 
@@ -323,12 +543,12 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
                 if within_active_area {
                     // began the line
                     if lineidx >= 32 {
-                        b"There must by exactly 32 lines in each area"[999];
+                        area_error(src.name, lineidx, 0, chr, "there must be exactly 32 lines in each area");
                     }
                 } else {
                     // finished the line
                     if cellidx != 128 {
-                        b"Each line of the area must be exactly 128 characters long"[999];
+                        area_error(src.name, lineidx, cellidx, chr, "each line of the area must be exactly 128 characters long");
                     }
                     cellidx = 0;
                     lineidx += 1;
@@ -336,7 +556,7 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
             }
             _ if within_active_area => {
                 if cellidx >= 128 {
-                    b"Each line of the area must be exactly 128 characters long"[999];
+                    area_error(src.name, lineidx, cellidx, chr, "each line of the area must be exactly 128 characters long");
                 }
                 
                 let room_x = cellidx / 16;
@@ -350,14 +570,17 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
                     b',' => (Empty, Solid),
                     b'X' => (Solid, Solid),
                     _ => {
-                        let info = lookup_char(chr, src.char_lookup);
+                        let info = match lookup_char(chr, src.char_lookup) {
+                            Some(x) => x,
+                            None => area_error(src.name, lineidx, cellidx, chr, "unknown character, it is not described in char_lookup"),
+                        };
                         
                         match (info.upper, info.lower) {
                             (CustomA, CustomB) | (CustomB, CustomA) => {
-                                b"This combination of low-level cell types is not allowed"[999];
+                                area_error(src.name, lineidx, cellidx, chr, "custom cell types A and B cannot be combined in one character");
                             }
                             (Special, Special) => {
-                                b"Unique items cannot be vertically stacked as pairs"[999];
+                                area_error(src.name, lineidx, cellidx, chr, "unique items cannot be vertically stacked as pairs");
                             }
                             _ => (),
                         }
@@ -367,22 +590,28 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
                             Solid => (),
                             Special => (),
                             CustomA => {
-                                let tt = lookup_tt(chr, src.tile_lookup);
+                                let tt = match lookup_tt(chr, src.tile_lookup) {
+                                    Some(x) => x,
+                                    None => area_error(src.name, lineidx, cellidx, chr, "character is not mapped to a tile type in tile_lookup"),
+                                };
                                 match  meta[roomidx].block_type_a {
                                     None => meta[roomidx].block_type_a = Some(tt),
                                     Some(x) if tile_type_enum_eq(x, tt) => (),
                                     _ => {
-                                        b"Room overloaded with special tile types for type A"[999];
+                                        area_error(src.name, lineidx, cellidx, chr, "room overloaded with custom tile types: it already uses another one for type A");
                                     }
                                 }
                             }
                             CustomB => {
-                                let tt = lookup_tt(chr, src.tile_lookup);
+                                let tt = match lookup_tt(chr, src.tile_lookup) {
+                                    Some(x) => x,
+                                    None => area_error(src.name, lineidx, cellidx, chr, "character is not mapped to a tile type in tile_lookup"),
+                                };
                                 match  meta[roomidx].block_type_b {
                                     None => meta[roomidx].block_type_b = Some(tt),
                                     Some(x) if tile_type_enum_eq(x, tt) => (),
                                     _ => {
-                                        b"Room overloaded with special tile types for type B"[999];
+                                        area_error(src.name, lineidx, cellidx, chr, "room overloaded with custom tile types: it already uses another one for type B");
                                     }
                                 }
                             }
@@ -393,22 +622,28 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
                             Solid => (),
                             Special => (),
                             CustomA => {
-                                let tt = lookup_tt(chr, src.tile_lookup);
+                                let tt = match lookup_tt(chr, src.tile_lookup) {
+                                    Some(x) => x,
+                                    None => area_error(src.name, lineidx, cellidx, chr, "character is not mapped to a tile type in tile_lookup"),
+                                };
                                 match  meta[roomidx].block_type_a {
                                     None => meta[roomidx].block_type_a = Some(tt),
                                     Some(x) if tile_type_enum_eq(x, tt) => (),
                                     _ => {
-                                        b"Room overloaded with special tile types for type A"[999];
+                                        area_error(src.name, lineidx, cellidx, chr, "room overloaded with custom tile types: it already uses another one for type A");
                                     }
                                 }
                             }
                             CustomB => {
-                                let tt = lookup_tt(chr, src.tile_lookup);
+                                let tt = match lookup_tt(chr, src.tile_lookup) {
+                                    Some(x) => x,
+                                    None => area_error(src.name, lineidx, cellidx, chr, "character is not mapped to a tile type in tile_lookup"),
+                                };
                                 match  meta[roomidx].block_type_b {
                                     None => meta[roomidx].block_type_b = Some(tt),
                                     Some(x) if tile_type_enum_eq(x, tt) => (),
                                     _ => {
-                                        b"Room overloaded with special tile types for type B"[999];
+                                        area_error(src.name, lineidx, cellidx, chr, "room overloaded with custom tile types: it already uses another one for type B");
                                     }
                                 }
                             }
@@ -422,34 +657,56 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
                 let within_room_y = lineidx % 8;
 
                 if matches!(upper, Special)  || matches!(lower, Special) {
-                    if matches!(upper, Special) {
-                        special_positions[special_position_index] = Some(UniqueItemPositionLowlevel {
-                            chr,
-                            pos: (cellidx, 2*lineidx),
-                        });
-                    }
-                    if matches!(lower, Special) {
-                        special_positions[special_position_index] = Some(UniqueItemPositionLowlevel {
-                            chr,
-                            pos: (cellidx, 2*lineidx+1),
-                        });
-                    }
-                    special_position_index+=1;
+                    if let Some(kind) = lookup_platform(chr, src.platform_lookup) {
+                        if platform_index >= MAX_PLATFORMS_PER_AREA {
+                            area_error(src.name, lineidx, cellidx, chr, "too many platforms in the area");
+                        }
+                        let pos = if matches!(upper, Special) { (cellidx, 2*lineidx) } else { (cellidx, 2*lineidx+1) };
+                        platforms[platform_index] = Some(PlatformPosition { kind, pos });
+                        platform_index += 1;
+                    } else if let Some(kind) = lookup_enemy(chr, src.enemy_lookup) {
+                        if enemy_index >= MAX_ENEMIES_PER_AREA {
+                            area_error(src.name, lineidx, cellidx, chr, "too many enemies in the area");
+                        }
+                        let pos = if matches!(upper, Special) { (cellidx, 2*lineidx) } else { (cellidx, 2*lineidx+1) };
+                        enemies[enemy_index] = Some(EnemyPosition { kind, pos });
+                        enemy_index += 1;
+                    } else {
+                        if special_position_index >= MAX_UNIQUE_ITEM_POSITIONS {
+                            area_error(src.name, lineidx, cellidx, chr, "too many unique items in the area");
+                        }
+                        if matches!(upper, Special) {
+                            special_positions[special_position_index] = Some(UniqueItemPositionLowlevel {
+                                chr,
+                                pos: (cellidx, 2*lineidx),
+                            });
+                        }
+                        if matches!(lower, Special) {
+                            special_positions[special_position_index] = Some(UniqueItemPositionLowlevel {
+                                chr,
+                                pos: (cellidx, 2*lineidx+1),
+                            });
+                        }
+                        special_position_index+=1;
 
 
-                    let (item, _prio) = lookup_unique(chr, src.item_lookup);
+                        let item = match lookup_unique(chr, src.item_lookup) {
+                            Some((item, _prio)) => item,
+                            None => area_error(src.name, lineidx, cellidx, chr, "special character is not mapped to UniqueItem in item_lookup nor to a platform in platform_lookup nor to an enemy in enemy_lookup"),
+                        };
 
-                    let mut k = 0;
-                    while k < uniques[roomidx].len() {
-                        if uniques[roomidx][k].is_none() {
-                            uniques[roomidx][k] = Some(item);
-                            break;
+                        let mut k = 0;
+                        while k < uniques[roomidx].len() {
+                            if uniques[roomidx][k].is_none() {
+                                uniques[roomidx][k] = Some(item);
+                                break;
+                            }
+                            k+=1;
                         }
-                        k+=1;
-                    }
 
-                    if k == uniques[roomidx].len() {
-                        b"Too many unique items in a room"[999];
+                        if k == uniques[roomidx].len() {
+                            area_error(src.name, lineidx, cellidx, chr, "too many unique items in a room");
+                        }
                     }
                 } 
                
@@ -465,17 +722,31 @@ const fn makearea<const C:usize, const T:usize, const I:usize>(src: AreaSource<C
         i += 1;
     }  
     if lineidx != 32 {
-        b"There must by exactly 32 lines in each area"[999];
+        ConstMsg::new("Area ").s(src.name).s(" has ").n(lineidx as usize).s(" lines instead of 32").panic();
     }
 
-    (buf, special_positions, meta, uniques)
+    ParsedArea {
+        rooms: buf,
+        specials: special_positions,
+        meta,
+        uniques,
+        platforms,
+        enemies,
+    }
 }
 
 
 impl Area {
-    pub const fn build<const C: usize, const T: usize, const I:usize>(src: AreaSource<C,T,I>) -> (Area, UniqueItemPositions) {  
+    pub const fn build<const C: usize, const T: usize, const I:usize, const P:usize, const E:usize>(src: AreaSource<C,T,I,P,E>) -> (Area, UniqueItemPositions) {  
         let item_lookup = src.item_lookup;   
-        let (rooms, specials_ll, meta, uniques) = makearea(src);
+        let music = src.music;
+        let background = src.background;
+        let mut i = 0;
+        while i < background.len() {
+            background[i].check(src.name);
+            i += 1;
+        }
+        let ParsedArea { rooms, specials: specials_ll, meta, uniques, platforms, enemies } = makearea(src);
 
         let mut specials = [None; MAX_UNIQUE_ITEM_POSITIONS];
 
@@ -483,7 +754,11 @@ impl Area {
         let mut j: usize = 0;
         while i < specials_ll.len() {
             if let Some(spcll) = specials_ll[i] {
-                let (item, priority) = lookup_unique(spcll.chr, item_lookup);
+                // makearea have already checked that the character is mapped
+                let (item, priority) = match lookup_unique(spcll.chr, item_lookup) {
+                    Some(x) => x,
+                    None => (UniqueItem::PlayerStart, false),
+                };
                 specials[j] = Some(UniqueItemPosition{item, pos:spcll.pos, priority});
                 j+=1;
             }
@@ -494,59 +769,146 @@ impl Area {
             rooms,
             meta,
             uniques,
+            music,
+            background,
+            platforms,
+            enemies,
         }, specials)
     }
 }
 
 impl Level {
     pub const fn new() -> Level {
+        unique_items::check_items_table(unique_items::ITEMS);
+
         let mut unique_items = [(UniqueItem::PlayerStart, (0,0)); UniqueItem::VARIANT_COUNT];
         let mut prioritized = [false; UniqueItem::VARIANT_COUNT];
 
+        let mut areas = [PlacedArea { area: level::AREAS[0].0.0, offset: level::AREAS[0].1 }; level::AREAS.len()];
+        let mut size_in_areas = (0, 0);
+        let mut platforms = [None; MAX_PLATFORMS_PER_AREA * level::AREAS.len()];
+        let mut enemies = [None; MAX_ENEMIES_PER_AREA * level::AREAS.len()];
+
         let mut i = 0;
 
-        let mut j = 0;
-        let specials = level::AREA1.1;
-        while j < specials.len() {
-            if let Some(UniqueItemPosition { item, pos, priority}) = specials[j] {
-                let mut insert_at_the_end = true;
+        let mut n = 0;
+        while n < level::AREAS.len() {
+            let (&(area, specials), offset) = level::AREAS[n];
 
-                let mut k = 0;
-                while k < i {
-                    if unique_items[k].0 as u8== item as u8 {
-                        insert_at_the_end = false;
-                        match (priority, prioritized[k]) {
-                            (false, false) => {b"Duplicate position for an unique item"[999];}
-                            (false, true) => (), // silently ignore non-priority position when priority one is already set
-                            (true, false) => {
-                                unique_items[k].1 = pos;
-                                prioritized[k] = true;
+            let mut m = 0;
+            while m < n {
+                if areas[m].offset.0 == offset.0 && areas[m].offset.1 == offset.1 {
+                    ConstMsg::new("Areas #").n(m).s(" and #").n(n).s(" are both placed at area grid position ").pos(offset).panic();
+                }
+                m += 1;
+            }
+            areas[n] = PlacedArea { area, offset };
+            if offset.0 + 1 > size_in_areas.0 { size_in_areas.0 = offset.0 + 1; }
+            if offset.1 + 1 > size_in_areas.1 { size_in_areas.1 = offset.1 + 1; }
+
+            let mut j = 0;
+            while j < MAX_PLATFORMS_PER_AREA {
+                if let Some(PlatformPosition { kind, pos }) = area.platforms[j] {
+                    let pos = (pos.0 + offset.0 * AREA_SIZE.0, pos.1 + offset.1 * AREA_SIZE.1);
+                    platforms[n * MAX_PLATFORMS_PER_AREA + j] = Some(PlatformPosition { kind, pos });
+                }
+                j += 1;
+            }
+
+            let mut j = 0;
+            while j < MAX_ENEMIES_PER_AREA {
+                if let Some(EnemyPosition { kind, pos }) = area.enemies[j] {
+                    let pos = (pos.0 + offset.0 * AREA_SIZE.0, pos.1 + offset.1 * AREA_SIZE.1);
+                    enemies[n * MAX_ENEMIES_PER_AREA + j] = Some(EnemyPosition { kind, pos });
+                }
+                j += 1;
+            }
+
+            let mut j = 0;
+            while j < specials.len() {
+                if let Some(UniqueItemPosition { item, pos, priority}) = specials[j] {
+                    let pos = (pos.0 + offset.0 * AREA_SIZE.0, pos.1 + offset.1 * AREA_SIZE.1);
+                    let mut insert_at_the_end = true;
+
+                    let mut k = 0;
+                    while k < i {
+                        if unique_items[k].0 as u8== item as u8 {
+                            insert_at_the_end = false;
+                            match (priority, prioritized[k]) {
+                                (false, false) => {
+                                    ConstMsg::new("Duplicate position for unique item ").s(item.name())
+                                        .s(": ").pos(unique_items[k].1).s(" and ").pos(pos).panic();
+                                }
+                                (false, true) => (), // silently ignore non-priority position when priority one is already set
+                                (true, false) => {
+                                    unique_items[k].1 = pos;
+                                    prioritized[k] = true;
+                                }
+                                (true, true) => {
+                                    ConstMsg::new("Duplicate priority position for unique item ").s(item.name())
+                                        .s(": ").pos(unique_items[k].1).s(" and ").pos(pos).panic();
+                                }
                             }
-                            (true, true) => {b"Duplicate priority position for an unique item"[999];}
                         }
+                        k+=1;
+                    }
+                    
+                    if insert_at_the_end {
+                        unique_items[i].0 = item;
+                        unique_items[i].1 = pos;
+                        prioritized[i] = priority;
+                        i+=1;
                     }
-                    k+=1;
-                }
-                
-                if insert_at_the_end {
-                    unique_items[i].0 = item;
-                    unique_items[i].1 = pos;
-                    prioritized[i] = priority;
-                    i+=1;
                 }
+                j += 1;
             }
-            j += 1;
+            n += 1;
         }
         if i != UniqueItem::VARIANT_COUNT {
-            b"There is a missing unique item on the level"[999];
+            let mut missing = 0;
+            'search: while missing < UniqueItem::VARIANT_COUNT {
+                let mut k = 0;
+                while k < i {
+                    if unique_items[k].0 as usize == missing {
+                        missing += 1;
+                        continue 'search;
+                    }
+                    k += 1;
+                }
+                break;
+            }
+            ConstMsg::new("Unique item ").s(unique_items::info_by_ordinal(missing).name).s(" is missing on the level").panic();
         }
 
         Level {
-            the_area: level::AREA1.0,
+            areas,
+            size_in_areas,
             unique_items,
+            platforms,
+            enemies,
         }
     }
 
+    /// Size of the level in tiles
+    pub const fn size(&self) -> TilePos {
+        (self.size_in_areas.0 * AREA_SIZE.0, self.size_in_areas.1 * AREA_SIZE.1)
+    }
+
+    /// Find the area containing given room (in level-wide room coordinates) and index of the room within that area.
+    /// Returns `None` for holes in the area grid.
+    pub const fn room(&self, room_x: u16, room_y: u16) -> Option<(&Area, usize)> {
+        let area_x = room_x / 8;
+        let area_y = room_y / 4;
+        let mut n = 0;
+        while n < self.areas.len() {
+            if self.areas[n].offset.0 == area_x && self.areas[n].offset.1 == area_y {
+                return Some((&self.areas[n].area, ((room_y % 4) * 8 + room_x % 8) as usize));
+            }
+            n += 1;
+        }
+        None
+    }
+
     pub const fn unique_item_pos(&self, item: UniqueItem) -> TilePos {
         let mut i=0;
         while i < self.unique_items.len() {
@@ -555,9 +917,8 @@ impl Level {
             }
             i+=1;
         }
-        #[allow(unconditional_panic)]
-        b"Internal error: Level::new should have caught missing item position"[999];
-        (0,0)
+        // runs every frame too, so no formatted message here: `Level::new` reports the item by name
+        panic!("Internal error: Level::new should have caught a missing unique item position")
     }
 }
 
@@ -642,6 +1003,9 @@ pub const fn ll_char_descriptions<const N: usize>(specifier: &'static [u8]) -> [
             S::Idle =>  match specifier[i] {
                 b' ' | b'\n' | b'\t' => S::Idle,
                 x => {
+                    if j >= v.len() {
+                        ConstMsg::new("Char lookup specifier has more triplets than declared ").n(v.len()).s(", extra one starts with ").c(x).panic();
+                    }
                     v[j].chr = x;
                     S::Upper
                 }
@@ -653,9 +1017,9 @@ pub const fn ll_char_descriptions<const N: usize>(specifier: &'static [u8]) -> [
                     b'A' => LowlevelCellType::CustomA,
                     b'B' => LowlevelCellType::CustomB,
                     b'!' => LowlevelCellType::Special,
-                    _ => {
-                        b"Invalid low-level cell type letter"[999];
-                        LowlevelCellType::Empty
+                    x => {
+                        ConstMsg::new("Invalid low-level cell type letter ").c(x).s(" for character ").c(v[j].chr)
+                            .s(" in char lookup specifier, expected one of `.XAB!`").panic();
                     }
                 };
                 match s {
@@ -674,8 +1038,137 @@ pub const fn ll_char_descriptions<const N: usize>(specifier: &'static [u8]) -> [
         };
         i+=1;
     }
+    if !matches!(s, S::Idle) {
+        ConstMsg::new("Char lookup specifier ends with incomplete triplet for character ").c(v[j].chr).panic();
+    }
     if j != v.len() {
-        b"Mismatch between declared length of low-level cell types and actual number of letter triplets"[999];
+        ConstMsg::new("Char lookup specifier has ").n(j).s(" triplets, but ").n(v.len()).s(" were declared").panic();
     }
     v
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Area text made of empty cells, with some characters replaced: `(line, column, character)`
+    fn area_text(edits: &[(usize, usize, u8)]) -> &'static [u8] {
+        let mut lines = vec![vec![b' '; 128]; 32];
+        for &(line, column, chr) in edits {
+            lines[line][column] = chr;
+        }
+        let mut text = vec![b'\n'];
+        for line in lines {
+            text.push(b'|');
+            text.extend(line);
+            text.extend(b"|\n");
+        }
+        Box::leak(text.into_boxed_slice())
+    }
+
+    fn build(cells: &'static [u8]) {
+        Area::build(AreaSource {
+            name: "TEST",
+            cells,
+            empty_tile_style: tile_type!(EmptyTile),
+            solid_tile_style: tile_type!(UsualArea1Tile),
+            music: None,
+            camera: [CameraMode::Follow; 32],
+            background: &[],
+            char_lookup: ll_char_descriptions::<5>(b"J.A L.A j.B i.! c.!"),
+            tile_lookup: tile_types_mapping![(JumpyTile J j) (Ladder1Tile L)],
+            item_lookup: unique_items_mapping![(InfoWelcome i) (CrateLog c)],
+            platform_lookup: [],
+            enemy_lookup: [],
+        });
+    }
+
+    #[test]
+    fn const_msg() {
+        let msg = ConstMsg::new("a").n(0).s(" ").n(1203).s(" ").c(b'x').s(" ").c(b'\n').s(" ").pos((3, 4));
+        assert_eq!(msg.as_str(), "a0 1203 'x' byte 10 (3, 4)");
+    }
+
+    #[test]
+    fn valid_area() {
+        build(area_text(&[(0, 0, b'X'), (5, 17, b'J'), (5, 18, b'j'), (9, 40, b'i'), (9, 41, b'c')]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Area TEST, line 4, column 21 (room 1), character '?': unknown character")]
+    fn unknown_char() {
+        build(area_text(&[(3, 20, b'?')]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Area TEST, line 9, column 128 (room 15), character '|': each line of the area must be exactly 128 characters long")]
+    fn short_line() {
+        let mut text = area_text(&[]).to_vec();
+        // drop the last cell of the 9th line
+        text.remove(1 + 8 * 131 + 128);
+        build(Box::leak(text.into_boxed_slice()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Area TEST, line 2, column 4 (room 0), character 'L': room overloaded with custom tile types")]
+    fn room_overloaded() {
+        build(area_text(&[(1, 2, b'J'), (1, 3, b'L')]));
+    }
+
+    #[test]
+    #[should_panic(expected = "character 'c': too many unique items in a room")]
+    fn too_many_items_in_room() {
+        build(area_text(&[(1, 2, b'i'), (1, 3, b'c'), (1, 4, b'c')]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Char lookup specifier has 1 triplets, but 2 were declared")]
+    fn char_lookup_too_short() {
+        ll_char_descriptions::<2>(b"J.A");
+    }
+
+    #[test]
+    #[should_panic(expected = "8x8 sprite line 2, column 14, character '|': there must be exactly 15 characters")]
+    fn sprite_line_too_short() {
+        sprite8x8(b"
+            |. . . . . . . .|
+            |. . . . . . .|
+        ");
+    }
+
+    #[test]
+    #[should_panic(expected = "Room line 1, column 3, character '#': undefined character")]
+    fn room_unknown_char() {
+        room16x16(b"|  #             |");
+    }
+
+    #[test]
+    fn sprites_of_any_size() {
+        let text = b"
+            |. X . . X . . .|
+            |. . X X X X . X|
+            |. X . . . . X .|
+            |X X . . . . X .|
+            |. X . . . . X X|
+            |. X . . . . X .|
+            |X . X X X X . .|
+            |. . . X . . X .|
+        ";
+        assert_eq!(sprite1bpp::<8>(8, 8, text), sprite8x8(text));
+        // rows are not padded to whole bytes
+        assert_eq!(sprite1bpp::<2>(3, 3, b"|X . X|\n|. X .|\n|X X X|"), [0b0101_0100, 0b0000_0000]);
+        assert_eq!(sprite2bpp::<3>(3, 3, b"|. - o|\n|X . .|\n|. . X|"), [0b00_01_10_11, 0b00_00_00_00, 0b11_000000]);
+    }
+
+    #[test]
+    #[should_panic(expected = "2BPP sprite line 2, column 3, character 'x': unknown glyph")]
+    fn sprite_unknown_glyph() {
+        sprite2bpp::<1>(2, 2, b"|X X|\n|o x|");
+    }
+
+    #[test]
+    #[should_panic(expected = "2BPP sprite of 16x8 takes 32 bytes, not 16")]
+    fn sprite_wrong_size() {
+        sprite2bpp::<16>(16, 8, b"");
+    }
+}
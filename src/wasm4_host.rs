@@ -0,0 +1,405 @@
+//
+// Software stand-in for WASM-4 API, used instead of `wasm4.rs` when building for a host (e.g. `cargo test-host`).
+//
+// Memory-mapped registers point into an ordinary static and drawing functions render into its framebuffer
+// the way WASM-4 runtime does, so the game can run headlessly and tests can inspect pixels.
+
+use std::ptr::{addr_of, addr_of_mut};
+use std::sync::{Mutex, MutexGuard};
+
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │ Platform Constants                                                        │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+pub const SCREEN_SIZE: u32 = 160;
+
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │ Memory Addresses                                                          │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+struct Memory {
+    palette: [u32; 4],
+    draw_colors: u16,
+    gamepads: [u8; 4],
+    mouse_x: i16,
+    mouse_y: i16,
+    mouse_buttons: u8,
+    framebuffer: [u8; 6400],
+    disk: [u8; 1024],
+    disk_len: usize,
+    /// Arguments of `tone` calls since the last `take_tones`
+    tones: Vec<[u32; 4]>,
+}
+
+const INITIAL_MEMORY: Memory = Memory {
+    palette: [0xe0f8cf, 0x86c06c, 0x306850, 0x071821],
+    draw_colors: 0x1203,
+    gamepads: [0; 4],
+    mouse_x: 0,
+    mouse_y: 0,
+    mouse_buttons: 0,
+    framebuffer: [0; 6400],
+    disk: [0; 1024],
+    disk_len: 0,
+    tones: Vec::new(),
+};
+
+static mut MEMORY: Memory = INITIAL_MEMORY;
+
+pub static mut PALETTE: *mut [u32; 4] = unsafe { addr_of_mut!(MEMORY.palette) };
+pub const DRAW_COLORS: *mut u16 = unsafe { addr_of_mut!(MEMORY.draw_colors) };
+pub const GAMEPAD1: *const u8 = unsafe { addr_of!(MEMORY.gamepads[0]) };
+pub const GAMEPAD2: *const u8 = unsafe { addr_of!(MEMORY.gamepads[1]) };
+pub const GAMEPAD3: *const u8 = unsafe { addr_of!(MEMORY.gamepads[2]) };
+pub const GAMEPAD4: *const u8 = unsafe { addr_of!(MEMORY.gamepads[3]) };
+pub const MOUSE_X: *const i16 = unsafe { addr_of!(MEMORY.mouse_x) };
+pub const MOUSE_Y: *const i16 = unsafe { addr_of!(MEMORY.mouse_y) };
+pub const MOUSE_BUTTONS: *const u8 = unsafe { addr_of!(MEMORY.mouse_buttons) };
+pub static mut FRAMEBUFFER: *mut [u8; 6400] = unsafe { addr_of_mut!(MEMORY.framebuffer) };
+
+pub const BUTTON_1: u8 = 1;
+pub const BUTTON_2: u8 = 2;
+pub const BUTTON_LEFT: u8 = 16;
+pub const BUTTON_RIGHT: u8 = 32;
+pub const BUTTON_UP: u8 = 64;
+pub const BUTTON_DOWN: u8 = 128;
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Get exclusive access to the emulated memory, reset to its initial state.
+/// Everything touching the framebuffer or disk from tests should hold the guard.
+pub fn exclusive() -> MutexGuard<'static, ()> {
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    reset();
+    guard
+}
+
+/// Reset the emulated memory, including the disk, to its initial state
+pub fn reset() {
+    unsafe {
+        *addr_of_mut!(MEMORY) = INITIAL_MEMORY;
+    }
+}
+
+/// Palette index (0..=3) of a pixel in the framebuffer
+pub fn get_pixel(x: u32, y: u32) -> u8 {
+    let idx = (y * SCREEN_SIZE + x) as usize;
+    unsafe {
+        ((*FRAMEBUFFER)[idx >> 2] >> ((idx & 0b11) << 1)) & 0b11
+    }
+}
+
+fn set_pixel(x: i32, y: i32, colour: u8) {
+    if x < 0 || y < 0 || x >= SCREEN_SIZE as i32 || y >= SCREEN_SIZE as i32 {
+        return;
+    }
+    let idx = (y as u32 * SCREEN_SIZE + x as u32) as usize;
+    let shift = (idx & 0b11) << 1;
+    unsafe {
+        let byte = &mut (*FRAMEBUFFER)[idx >> 2];
+        *byte = (*byte & !(0b11 << shift)) | ((colour & 0b11) << shift);
+    }
+}
+
+/// Value of `n`-th nibble of DRAW_COLORS: 0 means transparent, 1..=4 are palette indexes plus one
+fn draw_colour(n: u32) -> u8 {
+    unsafe { ((*DRAW_COLORS >> (n * 4)) & 0xF) as u8 }
+}
+
+fn hline(x1: i32, x2: i32, y: i32, dc: u8) {
+    for x in x1..x2 {
+        set_pixel(x, y, dc - 1);
+    }
+}
+
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │ Drawing Functions                                                         │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+/// Copies pixels to the framebuffer.
+pub fn blit (sprite: &[u8], x: i32, y: i32, width: u32, height: u32, flags: u32) {
+    blit_sub(sprite, x, y, width, height, 0, 0, width, flags)
+}
+
+/// Copies a subregion within a larger sprite atlas to the framebuffer.
+#[allow(clippy::too_many_arguments)]
+pub fn blit_sub (sprite: &[u8], x: i32, y: i32, width: u32, height: u32, src_x: u32, src_y: u32, stride: u32, flags: u32) {
+    let bpp2 = flags & BLIT_2BPP != 0;
+    let mut flip_x = flags & BLIT_FLIP_X != 0;
+    let flip_y = flags & BLIT_FLIP_Y != 0;
+    let rotate = flags & BLIT_ROTATE != 0;
+    if rotate {
+        flip_x = !flip_x;
+    }
+
+    for sy in 0..height {
+        for sx in 0..width {
+            let bx = src_x + if flip_x { width - sx - 1 } else { sx };
+            let by = src_y + if flip_y { height - sy - 1 } else { sy };
+            let bit = (by * stride + bx) as usize;
+            let colour_idx = if bpp2 {
+                (sprite[bit >> 2] >> (6 - ((bit & 0b11) << 1))) & 0b11
+            } else {
+                (sprite[bit >> 3] >> (7 - (bit & 0b111))) & 0b1
+            };
+            let dc = draw_colour(colour_idx as u32);
+            if dc != 0 {
+                if rotate {
+                    set_pixel(x + sy as i32, y + sx as i32, dc - 1);
+                } else {
+                    set_pixel(x + sx as i32, y + sy as i32, dc - 1);
+                }
+            }
+        }
+    }
+}
+
+pub const BLIT_2BPP: u32 = 1;
+pub const BLIT_1BPP: u32 = 0;
+pub const BLIT_FLIP_X: u32 = 2;
+pub const BLIT_FLIP_Y: u32 = 4;
+pub const BLIT_ROTATE: u32 = 8;
+
+/// Draws a line between two points.
+pub fn line (x1: i32, y1: i32, x2: i32, y2: i32) {
+    let dc = draw_colour(0);
+    if dc == 0 {
+        return;
+    }
+    let (mut x, mut y) = (x1, y1);
+    let dx = (x2 - x1).abs();
+    let dy = -(y2 - y1).abs();
+    let sx = if x1 < x2 { 1 } else { -1 };
+    let sy = if y1 < y2 { 1 } else { -1 };
+    let mut err = dx + dy;
+    loop {
+        set_pixel(x, y, dc - 1);
+        if x == x2 && y == y2 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Draws an oval (or circle).
+pub fn oval (x: i32, y: i32, width: u32, height: u32) {
+    let (fill, stroke) = (draw_colour(0), draw_colour(1));
+    let (a, b) = (width as f32 / 2.0, height as f32 / 2.0);
+    if a <= 0.0 || b <= 0.0 {
+        return;
+    }
+    let inner = |px: f32, py: f32, shrink: f32| {
+        let (ia, ib) = (a - shrink, b - shrink);
+        ia > 0.0 && ib > 0.0 && (px * px) / (ia * ia) + (py * py) / (ib * ib) <= 1.0
+    };
+    for py in 0..height as i32 {
+        for px in 0..width as i32 {
+            let (cx, cy) = (px as f32 + 0.5 - a, py as f32 + 0.5 - b);
+            if !inner(cx, cy, 0.0) {
+                continue;
+            }
+            if !inner(cx, cy, 1.0) {
+                if stroke != 0 {
+                    set_pixel(x + px, y + py, stroke - 1);
+                }
+            } else if fill != 0 {
+                set_pixel(x + px, y + py, fill - 1);
+            }
+        }
+    }
+}
+
+/// Draws a rectangle.
+pub fn rect (x: i32, y: i32, width: u32, height: u32) {
+    let (fill, stroke) = (draw_colour(0), draw_colour(1));
+    let (x2, y2) = (x + width as i32, y + height as i32);
+    if fill != 0 {
+        for yy in y..y2 {
+            hline(x, x2, yy, fill);
+        }
+    }
+    if stroke != 0 && width > 0 && height > 0 {
+        hline(x, x2, y, stroke);
+        hline(x, x2, y2 - 1, stroke);
+        for yy in y..y2 {
+            set_pixel(x, yy, stroke - 1);
+            set_pixel(x2 - 1, yy, stroke - 1);
+        }
+    }
+}
+
+/// Draws text using the built-in system font.
+///
+/// There is no font here: each character occupies an 8x8 cell like in WASM-4,
+/// but non-space characters are drawn as a hollow box. Enough to check text layout in tests.
+pub fn text (text: &str, x: i32, y: i32) {
+    let (fg, bg) = (draw_colour(0), draw_colour(1));
+    let (mut cx, mut cy) = (x, y);
+    for c in text.chars() {
+        if c == '\n' {
+            cx = x;
+            cy += 8;
+            continue;
+        }
+        for py in 0..8 {
+            for px in 0..8 {
+                let on_glyph = c != ' ' && (1..7).contains(&px) && (1..7).contains(&py)
+                    && (px == 1 || px == 6 || py == 1 || py == 6);
+                let dc = if on_glyph { fg } else { bg };
+                if dc != 0 {
+                    set_pixel(cx + px, cy + py, dc - 1);
+                }
+            }
+        }
+        cx += 8;
+    }
+}
+
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │ Sound Functions                                                           │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+/// Plays a sound tone. Silent on host, the call is only recorded for `take_tones`.
+pub fn tone (frequency: u32, volume: u32, duration: u32, flags: u32) {
+    unsafe {
+        (*addr_of_mut!(MEMORY.tones)).push([frequency, volume, duration, flags]);
+    }
+}
+
+/// Arguments (`frequency`, `volume`, `duration`, `flags`) of `tone` calls made since the previous call
+pub fn take_tones() -> Vec<[u32; 4]> {
+    unsafe {
+        std::mem::take(&mut *addr_of_mut!(MEMORY.tones))
+    }
+}
+
+pub const TONE_PULSE1: u32 = 0;
+pub const TONE_PULSE2: u32 = 1;
+pub const TONE_TRIANGLE: u32 = 2;
+pub const TONE_NOISE: u32 = 3;
+pub const TONE_MODE1: u32 = 0;
+pub const TONE_MODE2: u32 = 4;
+pub const TONE_MODE3: u32 = 8;
+pub const TONE_MODE4: u32 = 12;
+
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │ Storage Functions                                                         │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+/// Reads up to `size` bytes from persistent storage into the pointer `dest`.
+pub unsafe fn diskr (dest: *mut u8, size: u32) -> u32 {
+    let len = MEMORY.disk_len.min(size as usize);
+    std::ptr::copy_nonoverlapping(addr_of!(MEMORY.disk) as *const u8, dest, len);
+    len as u32
+}
+
+/// Writes up to `size` bytes from the pointer `src` into persistent storage.
+pub unsafe fn diskw (src: *const u8, size: u32) -> u32 {
+    let len = (size as usize).min(1024);
+    std::ptr::copy_nonoverlapping(src, addr_of_mut!(MEMORY.disk) as *mut u8, len);
+    MEMORY.disk_len = len;
+    len as u32
+}
+
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │ Other Functions                                                           │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+/// Copies `size` bytes from `srcPtr` into `destPtr`.
+pub unsafe fn memcpy (dest: *mut u8, src: *const u8, size: usize) -> usize {
+    std::ptr::copy(src, dest, size);
+    dest as usize
+}
+
+/// Fills memory at `destPtr` with `size` bytes of the fixed value `value`.
+pub unsafe fn memset (dest: *mut u8, byte: u8, size: usize) -> usize {
+    std::ptr::write_bytes(dest, byte, size);
+    dest as usize
+}
+
+/// Prints a message to the debug console.
+pub fn trace (text: &str) {
+    eprintln!("{}", text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blit_1bpp_uses_draw_colors() {
+        let _guard = exclusive();
+        unsafe { *DRAW_COLORS = 0x0042; }
+        blit(&[0b1000_0001], 10, 20, 8, 1, BLIT_1BPP);
+        assert_eq!(get_pixel(10, 20), 3);
+        assert_eq!(get_pixel(11, 20), 1);
+        assert_eq!(get_pixel(17, 20), 3);
+        assert_eq!(get_pixel(18, 20), 0);
+    }
+
+    #[test]
+    fn blit_transparent_and_flipped() {
+        let _guard = exclusive();
+        unsafe { *DRAW_COLORS = 0x0020; }
+        blit(&[0b1100_0000], -2, 0, 8, 1, BLIT_1BPP | BLIT_FLIP_X);
+        assert_eq!(get_pixel(4, 0), 1);
+        assert_eq!(get_pixel(5, 0), 1);
+        assert_eq!(get_pixel(3, 0), 0);
+    }
+
+    #[test]
+    fn blit_2bpp() {
+        let _guard = exclusive();
+        unsafe { *DRAW_COLORS = 0x4321; }
+        blit(&[0b00_01_10_11], 0, 0, 4, 1, BLIT_2BPP);
+        assert_eq!([get_pixel(0, 0), get_pixel(1, 0), get_pixel(2, 0), get_pixel(3, 0)], [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn rect_and_line() {
+        let _guard = exclusive();
+        unsafe { *DRAW_COLORS = 0x0032; }
+        rect(150, 150, 20, 4);
+        assert_eq!(get_pixel(150, 150), 2);
+        assert_eq!(get_pixel(151, 151), 1);
+        assert_eq!(get_pixel(159, 151), 1);
+        assert_eq!(get_pixel(151, 153), 2);
+
+        unsafe { *DRAW_COLORS = 0x0004; }
+        line(0, 0, 5, 5);
+        assert_eq!(get_pixel(3, 3), 3);
+        assert_eq!(get_pixel(3, 4), 0);
+    }
+
+    #[test]
+    fn disk_roundtrip() {
+        let _guard = exclusive();
+        let mut buf = [0u8; 4];
+        unsafe {
+            assert_eq!(diskr(buf.as_mut_ptr(), 4), 0);
+            diskw([1u8, 2, 3].as_ptr(), 3);
+            assert_eq!(diskr(buf.as_mut_ptr(), 4), 3);
+        }
+        assert_eq!(buf, [1, 2, 3, 0]);
+    }
+}
@@ -1,7 +1,11 @@
 
-use crate::{LEVEL, Level, MAX_UNIQUE_ITEMS_PER_ROOM, TilePos, UniqueItem, camera::Camera, cf32, tiles::{self, TileTypeEnum, TileType}, utils::draw_colours, wasm4::{SCREEN_SIZE, blit}};
+use crate::{Scalar, LEVEL, music::Track, platforms::{PlatformPosition, Platforms}, enemies::{EnemyPosition, Enemies}, Level, MAX_UNIQUE_ITEMS_PER_ROOM, TilePos, UniqueItem, camera::{Camera, CameraMode}, cf32, tiles::{self, Animation, TileTypeEnum, TileType}, utils::draw_colours, sprites::SpriteSheet, wasm4::{BLIT_1BPP, BLIT_2BPP, SCREEN_SIZE}};
 
-pub struct World {   
+pub struct World {
+    pub platforms: Platforms,
+    pub enemies: Enemies,
+    /// Tile the player bounced off and frames since, for `Animation::on_bounce`
+    bounced: Option<(TilePos, u8)>,
 }
 
 static THE_LEVEL : Level = LEVEL; 
@@ -9,32 +13,95 @@ static THE_LEVEL : Level = LEVEL;
 impl World {
     pub const fn new() -> Self {
         Self {
+            platforms: Platforms::new(),
+            enemies: Enemies::new(),
+            bounced: None,
+        }
+    }
 
+    /// Advance animations by a frame, restarting the one of the tile the player has just bounced off
+    pub fn animate(&mut self, bounced: Option<TilePos>) {
+        self.bounced = match bounced {
+            Some(pos) => Some((pos, 0)),
+            None => self.bounced.and_then(|(pos, since)| Some((pos, since.checked_add(1)?))),
+        };
+    }
+
+    /// Animation of the tile and its current frame, if it is playing.
+    /// `frame` must not wrap around often, or looping animations would skip when it does.
+    pub fn animation_frame(&self, tile: TileTypeEnum, pos: TilePos, frame: u32) -> Option<(&'static Animation, u32)> {
+        let animation = tile.animation()?;
+        if !animation.on_bounce {
+            return Some((animation, frame / animation.frame_time as u32 % animation.frames.len()));
+        }
+        match self.bounced {
+            Some((bounced, since)) if bounced == pos && ((since / animation.frame_time) as u32) < animation.frames.len() => {
+                Some((animation, (since / animation.frame_time) as u32))
+            }
+            _ => None,
         }
     }
 
-    pub const BOTTOM_RIGHT_TILE: TilePos = (16*8, 16*4);
+    pub const BOTTOM_RIGHT_TILE: TilePos = LEVEL.size();
 
-    pub fn draw(_global_frame: u8, player_coords:TilePos, cam: &Camera) {
+    pub fn draw(&self, frame: u32, player_coords:TilePos, cam: &Camera) {
         let (camx, camy) = World::to_world_coords(cam.pos);
+        if let Some((area, _)) = THE_LEVEL.room(camx >> 4, camy >> 4) {
+            for layer in area.background {
+                layer.draw(cam);
+            }
+        }
         let minx = camx.saturating_sub(9);
         let miny = camy.saturating_sub(9);
         for y in miny..(miny+19) {
             for x in minx..(minx+19) {
-                if let Some(sprite) =  World::get_tile((x,y)).sprite() {
-                    let mut col = 2;
-                    if (player_coords.0 as i32 - x as i32).abs() <= 1 && (player_coords.1 as i32 - y as i32).abs() <= 1  {
-                        col = 4;
-                    }
-                    let upperleft = (8.0 * cf32::new(x as f32, y as f32)) - cam.pos + cf32::new(0.5, 0.5) * SCREEN_SIZE as f32;
-                    if upperleft.re < 0.5 || upperleft.im < 0.5 || upperleft.re + 8.5 > SCREEN_SIZE as f32  || upperleft.im + 8.5 >= SCREEN_SIZE as f32 {
-                        continue;
-                    }
+                let tile = World::get_tile((x,y));
+                let (sheet, index) = match (self.animation_frame(tile, (x, y), frame), tile.sprite_2bpp(), tile.sprite()) {
+                    (Some((animation, index)), _, _) => (animation.frames, index),
+                    (None, Some(sprite), _) => (SpriteSheet { data: sprite, width: 8, frame: (8, 8), flags: BLIT_2BPP }, 0),
+                    (None, None, Some(sprite)) => (SpriteSheet { data: sprite, width: 8, frame: (8, 8), flags: BLIT_1BPP }, 0),
+                    (None, None, None) => continue,
+                };
+                let mut col = 2;
+                if (player_coords.0 as i32 - x as i32).abs() <= 1 && (player_coords.1 as i32 - y as i32).abs() <= 1  {
+                    col = 4;
+                }
+                let upperleft = cam.to_screen(8.0 * cf32::new(x as f32 + 0.5, y as f32 + 0.5)) - cf32::new(4.0, 4.0);
+                if upperleft.re < 0.5 || upperleft.im < 0.5 || upperleft.re + 8.5 > SCREEN_SIZE as f32  || upperleft.im + 8.5 >= SCREEN_SIZE as f32 {
+                    continue;
+                }
+                if sheet.flags == BLIT_2BPP {
+                    draw_colours(0, col, 3, 4);
+                } else {
                     draw_colours(col, 0, 0, 0);
-                    blit(sprite, upperleft.re as i32, upperleft.im as i32, 8, 8, 0);
                 }
+                sheet.blit(index, upperleft.re.to_f32() as i32, upperleft.im.to_f32() as i32, 0);
             }
         }
+        self.platforms.draw(cam);
+        self.enemies.draw(frame as u8, cam);
+    }
+
+    /// Platforms of the whole level, at their positions on frame 0
+    pub fn platforms() -> &'static [Option<PlatformPosition>] {
+        &THE_LEVEL.platforms
+    }
+
+    /// Enemies of the whole level, at their starting positions
+    pub fn enemies() -> &'static [Option<EnemyPosition>] {
+        &THE_LEVEL.enemies
+    }
+
+    pub fn music_at((x,y): TilePos) -> Option<&'static Track> {
+        THE_LEVEL.room(x >> 4, y >> 4).and_then(|(area, _)| area.music)
+    }
+
+    /// `CameraMode::Follow` outside of the level
+    pub fn camera_mode_at((x,y): TilePos) -> CameraMode {
+        match THE_LEVEL.room(x >> 4, y >> 4) {
+            Some((area, roomidx)) => area.meta[roomidx].camera,
+            None => CameraMode::Follow,
+        }
     }
 
     pub fn get_tile((x,y): TilePos) -> TileTypeEnum {
@@ -42,13 +109,15 @@ impl World {
             return tiles::EmptyTile.into();
         }
 
-        let room_x = x >> 4;
-        let room_y = y >> 4;
+        let (area, roomidx) = match THE_LEVEL.room(x >> 4, y >> 4) {
+            Some(x) => x,
+            None => return tiles::EmptyTile.into(),
+        };
         let within_room_x = x & 0xF;
         let within_room_y = y & 0xF;
 
-        let lowlevel_tile_type = ((THE_LEVEL.the_area.rooms[(room_y*8+room_x) as usize][within_room_y as usize] >> (within_room_x as usize*2)) & 0b11) as u8;
-        let meta = THE_LEVEL.the_area.meta[(room_y*8+room_x) as usize];
+        let lowlevel_tile_type = ((area.rooms[roomidx][within_room_y as usize] >> (within_room_x as usize*2)) & 0b11) as u8;
+        let meta = area.meta[roomidx];
         match lowlevel_tile_type {
             0 => meta.block_type_sp.unwrap(),
             1 => meta.block_type_x.unwrap(),
@@ -71,14 +140,21 @@ impl World {
 
         let minrx = room_x.saturating_sub(1);
         let minry = room_y.saturating_sub(1);
-        let mut maxrx = room_x + 1; if maxrx >= 8 { maxrx = 7; }
-        let mut maxry = room_y + 1; if maxry >= 4 { maxry = 3; }
+        let mut maxrx = room_x + 1; if maxrx >= World::BOTTOM_RIGHT_TILE.0 >> 4 { maxrx = (World::BOTTOM_RIGHT_TILE.0 >> 4) - 1; }
+        let mut maxry = room_y + 1; if maxry >= World::BOTTOM_RIGHT_TILE.1 >> 4 { maxry = (World::BOTTOM_RIGHT_TILE.1 >> 4) - 1; }
 
         let mut y : u16 = minry;
         while y <= maxry {
             let mut x : u16 = minrx;
             while x <= maxrx {
-                let items = LEVEL.the_area.uniques[(y*8+x) as usize];
+                let (area, roomidx) = match LEVEL.room(x, y) {
+                    Some(x) => x,
+                    None => {
+                        x += 1;
+                        continue;
+                    }
+                };
+                let items = area.uniques[roomidx];
                 let mut k = 0;
                 while k < items.len() {
                     if let Some(item) = items[k] {
@@ -105,16 +181,16 @@ impl World {
     pub fn to_world_coords(pos: cf32) -> TilePos {
         let x = match pos.re {
             t if t <= 4.0 => 0,
-            t if t >= 4.0 + 16.0*8.0*8.0 => 127,
+            t if t >= 4.0 + 8.0 * World::BOTTOM_RIGHT_TILE.0 as f32 => World::BOTTOM_RIGHT_TILE.0 - 1,
             t => {
-                ((t - 0.0) / 8.0) as u16
+                ((t - 0.0) / 8.0).to_f32() as u16
             }
         };
         let y = match pos.im {
             t if t <= 4.0 => 0,
-            t if t >= 4.0 + 16.0*8.0*4.0 => 63,
+            t if t >= 4.0 + 8.0 * World::BOTTOM_RIGHT_TILE.1 as f32 => World::BOTTOM_RIGHT_TILE.1 - 1,
             t => {
-                ((t - 0.0) / 8.0) as u16
+                ((t - 0.0) / 8.0).to_f32() as u16
             }
         };
         (x,y)